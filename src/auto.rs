@@ -0,0 +1,138 @@
+#![forbid(unsafe_code)]
+
+use std;
+
+use journald;
+use socket;
+use syslog;
+
+/// Backend chosen by `SyslogAppender::auto()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutoBackend {
+    /// Native protocol of journald, see `JournaldBackend`.
+    Journald,
+    /// RFC 3164 records sent directly to local syslog socket, `/dev/log` or another one from list, see
+    /// `SyslogAppenderBuilder::socket_paths()`.
+    DevLog,
+    /// `libc`'s syslog().
+    Libc,
+}
+
+impl AutoBackend {
+    /// Choose backend for the current environment.
+    ///
+    /// Journald is chosen if process is started by systemd (`JOURNAL_STREAM` or `INVOCATION_ID` environment variable
+    /// is set) and `/run/systemd/journal/socket` exists. Otherwise local syslog socket is used if one of `/dev/log`,
+    /// `/var/run/syslog` and `/var/run/log` accepts connection, and `libc`'s syslog() is the last resort.
+    pub fn detect() -> Self {
+        Self::detect_with_socket_paths(socket::DEFAULT_SOCKET_PATHS)
+    }
+
+    /// Like `detect()`, but with custom list of local syslog sockets, see `SyslogAppenderBuilder::socket_paths()`.
+    pub fn detect_with_socket_paths<S: AsRef<str>>(socket_paths: &[S]) -> Self {
+        Self::detect_with(
+            |name| std::env::var_os(name).is_some(),
+            |path| std::path::Path::new(path).exists(),
+            socket::unix_socket_available,
+            socket_paths,
+        )
+    }
+
+    fn detect_with<E, P, A, S>(env_is_set: E, path_exists: P, socket_available: A, socket_paths: &[S]) -> Self
+    where
+        E: Fn(&str) -> bool,
+        P: Fn(&str) -> bool,
+        A: Fn(&str) -> bool,
+        S: AsRef<str>,
+    {
+        let under_systemd = env_is_set("JOURNAL_STREAM") || env_is_set("INVOCATION_ID");
+        if under_systemd && path_exists(journald::JOURNALD_SOCKET) {
+            AutoBackend::Journald
+        } else if socket_paths.iter().any(|addr| socket_available(addr.as_ref())) {
+            AutoBackend::DevLog
+        } else {
+            AutoBackend::Libc
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            AutoBackend::Journald => "journald native protocol",
+            AutoBackend::DevLog => "syslog socket",
+            AutoBackend::Libc => "libc syslog()",
+        }
+    }
+}
+
+impl syslog::SyslogAppender {
+    /// Create builder with backend chosen by `AutoBackend::detect()`.
+    ///
+    /// The same configuration works on systemd hosts, in containers and in minimal images. Choice is logged with
+    /// `LOG_INFO` severity when appender is activated, use `SyslogAppenderBuilder::announce()` to disable this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate log4rs_syslog;
+    ///
+    /// let appender = log4rs_syslog::SyslogAppender::auto()
+    ///     .openlog("my-daemon", log4rs_syslog::LogOption::LOG_PID, log4rs_syslog::Facility::Daemon)
+    ///     .build();
+    /// ```
+    pub fn auto() -> syslog::SyslogAppenderBuilder {
+        let choice = AutoBackend::detect();
+        match choice {
+            AutoBackend::DevLog => Self::auto_builder(choice)
+                .builtin_backend(Box::new(socket::SocketBackend::unix_candidates(socket::DEFAULT_SOCKET_PATHS))),
+            AutoBackend::Journald | AutoBackend::Libc => Self::auto_builder(choice),
+        }
+    }
+
+    /// Like `auto()`, but with custom list of local syslog sockets, see `SyslogAppenderBuilder::socket_paths()`.
+    pub fn auto_with_socket_paths<S: AsRef<str>>(socket_paths: &[S]) -> syslog::SyslogAppenderBuilder {
+        let choice = AutoBackend::detect_with_socket_paths(socket_paths);
+        let builder = Self::auto_builder(choice);
+        match choice {
+            AutoBackend::DevLog => builder.socket_paths(socket_paths),
+            // List is still used by `emergency_log()`.
+            AutoBackend::Journald | AutoBackend::Libc => builder.candidate_socket_paths(socket_paths),
+        }
+    }
+
+    fn auto_builder(choice: AutoBackend) -> syslog::SyslogAppenderBuilder {
+        let builder = syslog::SyslogAppender::builder()
+            .config_kind(syslog::ConfigKind::AutoSyslog)
+            .announcement(format!("log4rs-syslog: using {}", choice.description()))
+            .announce(true);
+
+        match choice {
+            AutoBackend::Journald => builder.builtin_backend(Box::new(journald::JournaldBackend::new())),
+            AutoBackend::DevLog | AutoBackend::Libc => builder,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AutoBackend;
+
+    #[test]
+    fn detect() {
+        let detect = |env: &[&str], paths: &[&str]| {
+            AutoBackend::detect_with(
+                |name| env.contains(&name),
+                |path| paths.contains(&path),
+                |addr| paths.contains(&addr),
+                &["/dev/log", "@rsyslog"],
+            )
+        };
+
+        let all_paths = ["/run/systemd/journal/socket", "/dev/log"];
+        assert_eq!(detect(&["JOURNAL_STREAM"], &all_paths), AutoBackend::Journald);
+        assert_eq!(detect(&["INVOCATION_ID"], &all_paths), AutoBackend::Journald);
+        assert_eq!(detect(&[], &all_paths), AutoBackend::DevLog);
+        assert_eq!(detect(&["INVOCATION_ID"], &["/dev/log"]), AutoBackend::DevLog);
+        assert_eq!(detect(&[], &["@rsyslog"]), AutoBackend::DevLog);
+        assert_eq!(detect(&["JOURNAL_STREAM"], &[]), AutoBackend::Libc);
+    }
+}
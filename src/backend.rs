@@ -0,0 +1,40 @@
+use error;
+use libc;
+use syslog;
+
+/// Transport used by `SyslogAppender` to deliver messages, see `SyslogAppenderBuilder::backend()`.
+///
+/// Default backend uses `libc`'s openlog(), syslog() and closelog() functions. Custom backends may send messages
+/// over network, collect them in memory for tests, etc.
+pub trait SyslogBackend: Send + Sync {
+    /// Prepare for sending messages. Called once, before the first message or on `SyslogAppender::activate()`. If
+    /// this fails, it is called again before the next message.
+    ///
+    /// `ident` is the one passed to `SyslogAppenderBuilder::openlog()`, if any.
+    fn open(&self, ident: Option<&str>) -> Result<(), error::SyslogError>;
+
+    /// Send single message. `priority` is a combination of severity (`libc::LOG_EMERG`..`libc::LOG_DEBUG`) and
+    /// facility, facility is zero if not configured.
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), error::SyslogError>;
+
+    /// Flush buffered messages, if any.
+    fn flush(&self) -> Result<(), error::SyslogError>;
+
+    /// Release resources. Called when appender is dropped, but only if `open()` succeeded before.
+    fn close(&self);
+
+    /// Apply new log mask. Messages with severities not in mask are never passed to `send()`, so this is needed only
+    /// to share the mask with other users of the same transport.
+    fn set_log_mask(&self, _mask: Option<syslog::LogMask>) {}
+
+    /// Replace connections shared with the parent process by new ones. Called in the child process by `after_fork()`,
+    /// but only if `open()` succeeded before. Output of both processes would be interleaved otherwise, or go to a
+    /// peer closed by the parent.
+    fn after_fork(&self) {}
+
+    /// Describe where messages go after `open()`, like path of socket which backend is connected to. `None` if this
+    /// is not known.
+    fn destination(&self) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,326 @@
+use std;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+
+use fork;
+use ident;
+use libc;
+use syslog;
+
+// Maximum size of the whole record, including header. Longer messages are truncated.
+const MAX_RECORD_LEN: usize = 2048;
+
+struct Buffers {
+    ident: [u8; ident::MAX_IDENT_LEN],
+    ident_len: usize,
+    facility: libc::c_int,
+    record: [u8; MAX_RECORD_LEN],
+}
+
+struct EmergencyState {
+    // Simple try-lock. Never waits in signal handlers: if state is busy, message is dropped.
+    busy: AtomicBool,
+    fd: AtomicIsize,
+    buffers: std::cell::UnsafeCell<Buffers>,
+}
+
+// Access to `buffers` is guarded by `busy`.
+unsafe impl Sync for EmergencyState {}
+
+struct Registration {
+    id: usize,
+    ident: Option<String>,
+    facility: syslog::Facility,
+    socket_paths: Vec<String>,
+}
+
+/// Configurations of activated appenders. The most recent one is used, when it is unregistered, the previous one is
+/// restored. Never touched by `emergency_log()`.
+struct Registry {
+    registrations: Vec<Registration>,
+    next_id: usize,
+    // Candidate list which was used to open `STATE.fd`.
+    socket_paths: Vec<String>,
+}
+
+lazy_static! {
+    static ref REGISTRY: std::sync::Mutex<Registry> = std::sync::Mutex::new(Registry {
+        registrations: Vec::new(),
+        next_id: 1,
+        socket_paths: Vec::new(),
+    });
+}
+
+fn lock_registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+static STATE: EmergencyState = EmergencyState {
+    busy: AtomicBool::new(false),
+    fd: AtomicIsize::new(-1),
+    buffers: std::cell::UnsafeCell::new(Buffers {
+        ident: [0; ident::MAX_IDENT_LEN],
+        ident_len: 0,
+        facility: libc::LOG_USER,
+        record: [0; MAX_RECORD_LEN],
+    }),
+};
+
+struct Lock;
+
+impl Lock {
+    fn try_acquire() -> Option<Self> {
+        if STATE
+            .busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            Some(Lock)
+        } else {
+            None
+        }
+    }
+
+    fn acquire() -> Self {
+        loop {
+            if let Some(lock) = Self::try_acquire() {
+                return lock;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    fn buffers(&mut self) -> &mut Buffers {
+        unsafe { &mut *STATE.buffers.get() }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        STATE.busy.store(false, Ordering::Release);
+    }
+}
+
+// Fill `sun_path` and return length of address. Names starting with `@` are Linux abstract-namespace names.
+fn unix_addr(addr: &str, sockaddr: &mut libc::sockaddr_un) -> Option<libc::socklen_t> {
+    let path_offset = sockaddr.sun_path.as_ptr() as usize - sockaddr as *const libc::sockaddr_un as usize;
+
+    let (prefix_len, name) = match addr.strip_prefix('@') {
+        Some(name) if cfg!(target_os = "linux") => (1, name.as_bytes()),
+        Some(_) => return None,
+        // Terminating NUL is left by zeroing.
+        None => (0, addr.as_bytes()),
+    };
+    if prefix_len + name.len() >= sockaddr.sun_path.len() || (prefix_len == 0 && name.contains(&0)) {
+        return None;
+    }
+
+    for (dst, src) in sockaddr.sun_path[prefix_len..].iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+
+    Some(if prefix_len == 0 {
+        std::mem::size_of::<libc::sockaddr_un>()
+    } else {
+        path_offset + prefix_len + name.len()
+    } as libc::socklen_t)
+}
+
+fn open_socket(addr: &str) -> libc::c_int {
+    unsafe {
+        let mut sockaddr: libc::sockaddr_un = std::mem::zeroed();
+        sockaddr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+        let len = match unix_addr(addr, &mut sockaddr) {
+            Some(len) => len,
+            None => return -1,
+        };
+
+        let fd = libc::socket(libc::AF_UNIX, libc::SOCK_DGRAM, 0);
+        if fd < 0 {
+            return -1;
+        }
+        let _ = libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+
+        let res = libc::connect(
+            fd,
+            &sockaddr as *const libc::sockaddr_un as *const libc::sockaddr,
+            len,
+        );
+        if res < 0 {
+            let _ = libc::close(fd);
+            return -1;
+        }
+
+        fd
+    }
+}
+
+/// Remember ident and facility of appender for `emergency_log()` and pre-open the first socket from list which accepts
+/// connection. Called on appender activation, returns ID for `unregister_appender()`.
+pub(crate) fn register_appender<S: AsRef<str>>(
+    ident: Option<&str>,
+    facility: syslog::Facility,
+    socket_paths: &[S],
+) -> usize {
+    let mut registry = lock_registry();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.registrations.push(Registration {
+        id,
+        ident: ident.map(String::from),
+        facility,
+        socket_paths: socket_paths.iter().map(|addr| String::from(addr.as_ref())).collect(),
+    });
+    registry.apply_active();
+    id
+}
+
+/// Forget configuration of dropped appender. If it was the active one, configuration of the previously registered
+/// appender is restored.
+pub(crate) fn unregister_appender(id: usize) {
+    let mut registry = lock_registry();
+    if let Some(pos) = registry.registrations.iter().position(|registration| registration.id == id) {
+        let _ = registry.registrations.remove(pos);
+        if pos == registry.registrations.len() {
+            registry.apply_active();
+        }
+    }
+}
+
+impl Registry {
+    /// Socket is reopened if list differs from the one used before or if no socket could be opened last time.
+    fn apply_active(&mut self) {
+        let (ident, facility, socket_paths) = match self.registrations.last() {
+            Some(registration) => (
+                registration.ident.as_deref(),
+                registration.facility,
+                &registration.socket_paths[..],
+            ),
+            None => (None, syslog::Facility::User, &[][..]),
+        };
+
+        let reopen = STATE.fd.load(Ordering::Relaxed) < 0 || self.socket_paths[..] != *socket_paths;
+        let fd = if reopen {
+            Some(socket_paths
+                .iter()
+                .map(|addr| open_socket(addr))
+                .find(|&fd| fd >= 0)
+                .unwrap_or(-1))
+        } else {
+            None
+        };
+
+        let mut lock = Lock::acquire();
+
+        {
+            let buffers = lock.buffers();
+            let ident = ident.map_or(&[][..], str::as_bytes);
+            let ident_len = std::cmp::min(ident.len(), ident::MAX_IDENT_LEN);
+            buffers.ident[..ident_len].copy_from_slice(&ident[..ident_len]);
+            buffers.ident_len = ident_len;
+            buffers.facility = facility.into();
+        }
+
+        if let Some(fd) = fd {
+            let previous = STATE.fd.swap(fd as isize, Ordering::Relaxed);
+            if previous >= 0 {
+                let _ = unsafe { libc::close(previous as libc::c_int) };
+            }
+            self.socket_paths = socket_paths.to_vec();
+        }
+    }
+}
+
+struct RecordWriter<'a> {
+    record: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> RecordWriter<'a> {
+    fn write(&mut self, data: &[u8]) {
+        let len = std::cmp::min(data.len(), self.record.len() - self.len);
+        self.record[self.len..self.len + len].copy_from_slice(&data[..len]);
+        self.len += len;
+    }
+
+    fn write_decimal(&mut self, mut value: u64) {
+        let mut digits = [0u8; 20];
+        let mut pos = digits.len();
+        loop {
+            pos -= 1;
+            digits[pos] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        self.write(&digits[pos..]);
+    }
+}
+
+/// Send message to syslog from a context where the normal logging path can not be used, like signal handlers.
+///
+/// Uses only async-signal-safe functions: no memory allocation, no locking. The message is formatted into a static
+/// buffer as `<PRI>ident[pid]: message` and written to the syslog socket opened when `SyslogAppender` is activated,
+/// using ident and facility passed to `SyslogAppenderBuilder::openlog()` of the most recently activated appender,
+/// whatever backend it uses. Socket is the first one which accepts connection from the list set by
+/// `SyslogAppenderBuilder::socket_paths()`, or from `/dev/log`, `/var/run/syslog` and `/var/run/log`. When that
+/// appender is dropped, configuration of the previously activated one which is still alive is restored, after the last
+/// one the socket is closed. Records longer than 2048 bytes are truncated.
+///
+/// Fails with `std::io::ErrorKind::WouldBlock` if called concurrently from another thread or from a signal handler
+/// which interrupted another call to `emergency_log()`, and with `std::io::ErrorKind::NotConnected` if no appender
+/// was activated yet or the socket could not be opened.
+///
+/// # Examples
+///
+/// ```
+/// extern crate libc;
+/// extern crate log4rs_syslog;
+///
+/// extern "C" fn on_sigterm(_: libc::c_int) {
+///     let _ = log4rs_syslog::emergency_log(log4rs_syslog::Severity::Crit, b"Terminated by SIGTERM");
+///     unsafe { libc::_exit(1) };
+/// }
+///
+/// let appender = log4rs_syslog::SyslogAppender::builder().build();
+/// appender.activate().unwrap();
+/// unsafe { libc::signal(libc::SIGTERM, on_sigterm as libc::sighandler_t) };
+/// ```
+pub fn emergency_log(severity: syslog::Severity, message: &[u8]) -> std::io::Result<()> {
+    let mut lock = match Lock::try_acquire() {
+        Some(lock) => lock,
+        None => return Err(std::io::ErrorKind::WouldBlock.into()),
+    };
+
+    let fd = STATE.fd.load(Ordering::Relaxed) as libc::c_int;
+    if fd < 0 {
+        return Err(std::io::ErrorKind::NotConnected.into());
+    }
+
+    let buffers = lock.buffers();
+    let severity: libc::c_int = severity.into();
+    let mut writer = RecordWriter {
+        record: &mut buffers.record,
+        len: 0,
+    };
+    writer.write(b"<");
+    writer.write_decimal((buffers.facility | severity) as u64);
+    writer.write(b">");
+    if buffers.ident_len > 0 {
+        writer.write(&buffers.ident[..buffers.ident_len]);
+        writer.write(b"[");
+        writer.write_decimal(fork::pid() as u64);
+        writer.write(b"]: ");
+    }
+    writer.write(message);
+
+    let sent = unsafe { libc::send(fd, writer.record.as_ptr() as *const libc::c_void, writer.len, 0) };
+    if sent < 0 {
+        // Does not allocate.
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
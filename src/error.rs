@@ -0,0 +1,109 @@
+use std;
+
+/// Error returned by syslog appenders and their builders.
+#[derive(Debug)]
+pub enum SyslogError {
+    /// Encoder failed to format the message.
+    Encoder(Box<std::error::Error + Sync + Send>),
+
+    /// Failed to deliver the message to the system logger.
+    Io(std::io::Error),
+
+    /// Message is larger than the transport allows, like datagram which does not fit into socket's send buffer.
+    MessageTooLarge {
+        /// Size of the record in bytes, including header added by backend.
+        size: usize,
+        /// Maximum allowed size in bytes. For datagram sockets this is the size of send buffer, actual limit is
+        /// slightly lower.
+        limit: usize,
+    },
+
+    /// Message was dropped because of rate limiting. Never returned by built-in backends, intended for custom
+    /// `SyslogBackend` implementations which limit rate of messages.
+    RateLimited,
+
+    /// Message was logged, but openlog() configuration of another appender was active.
+    OpenlogConflict,
+
+    /// Ident passed to openlog() is not valid.
+    InvalidIdent {
+        /// The ident.
+        ident: String,
+        /// Why it is not valid.
+        reason: &'static str,
+    },
+
+    /// Invalid configuration value.
+    InvalidConfig {
+        /// Path to the invalid field, like `level_map.Trace`.
+        field: String,
+        /// Why the value is not valid.
+        reason: String,
+    },
+
+    /// Appender controlled by `SyslogAppenderHandle` was dropped.
+    AppenderDropped,
+}
+
+impl SyslogError {
+    /// Returns `true` if this error means that the message was not delivered.
+    pub fn is_dropped_message(&self) -> bool {
+        match *self {
+            SyslogError::Encoder(_) | SyslogError::Io(_) | SyslogError::MessageTooLarge { .. } |
+            SyslogError::RateLimited => true,
+            SyslogError::OpenlogConflict | SyslogError::InvalidIdent { .. } | SyslogError::InvalidConfig { .. } |
+            SyslogError::AppenderDropped => false,
+        }
+    }
+
+    /// Returns `true` if this error is caused by invalid configuration.
+    pub fn is_config_error(&self) -> bool {
+        match *self {
+            SyslogError::OpenlogConflict | SyslogError::InvalidIdent { .. } | SyslogError::InvalidConfig { .. } => {
+                true
+            },
+            SyslogError::Encoder(_) | SyslogError::Io(_) | SyslogError::MessageTooLarge { .. } |
+            SyslogError::RateLimited | SyslogError::AppenderDropped => false,
+        }
+    }
+}
+
+impl std::fmt::Display for SyslogError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SyslogError::Encoder(ref err) => write!(formatter, "Failed to encode message: {}", err),
+            SyslogError::Io(ref err) => write!(formatter, "Failed to send message: {}", err),
+            SyslogError::MessageTooLarge { size, limit } => write!(
+                formatter,
+                "Message is too large: {} bytes, limit is {} bytes",
+                size, limit
+            ),
+            SyslogError::RateLimited => formatter.write_str("Message dropped by rate limiter"),
+            SyslogError::OpenlogConflict => formatter.write_str(
+                "Conflicting openlog() configuration of another syslog appender is active, messages from this \
+                 appender are logged using it",
+            ),
+            SyslogError::InvalidIdent { ref ident, reason } => write!(formatter, "Invalid ident {:?}: {}", ident, reason),
+            SyslogError::InvalidConfig { ref field, ref reason } => {
+                write!(formatter, "Invalid value of \"{}\": {}", field, reason)
+            },
+            SyslogError::AppenderDropped => formatter.write_str("Appender was dropped"),
+        }
+    }
+}
+
+impl std::error::Error for SyslogError {
+    fn source(&self) -> Option<&(std::error::Error + 'static)> {
+        match *self {
+            SyslogError::Encoder(ref err) => Some(&**err),
+            SyslogError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SyslogError {
+    fn from(err: std::io::Error) -> Self {
+        SyslogError::Io(err)
+    }
+}
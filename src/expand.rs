@@ -0,0 +1,129 @@
+use std;
+
+use fork;
+use ident;
+
+fn hostname() -> Result<String, String> {
+    ident::hostname().map_err(|err| format!("Unable to get hostname: {}", err))
+}
+
+fn exe() -> Result<String, String> {
+    let path = std::env::current_exe().map_err(|err| format!("Unable to get executable name: {}", err))?;
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("Unable to get executable name from path {:?}", path))
+}
+
+fn builtin(name: &str) -> Result<String, String> {
+    match name {
+        "pid" => Ok(fork::pid().to_string()),
+        "exe" => exe(),
+        "hostname" => hostname(),
+        unknown => Err(format!(
+            "Unknown variable \"{{{}}}\", expected one of: {{pid}}, {{exe}}, {{hostname}}",
+            unknown
+        )),
+    }
+}
+
+fn env_var(expr: &str) -> Result<String, String> {
+    let (name, default) = match expr.find(":-") {
+        Some(pos) => (&expr[..pos], Some(&expr[pos + 2..])),
+        None => (expr, None),
+    };
+
+    if name.is_empty() {
+        return Err(String::from("Empty environment variable name"));
+    }
+
+    match (std::env::var(name), default) {
+        (Ok(ref value), Some(default)) if value.is_empty() => Ok(String::from(default)),
+        (Ok(value), _) => Ok(value),
+        (Err(std::env::VarError::NotPresent), Some(default)) => Ok(String::from(default)),
+        (Err(err), _) => Err(format!("Environment variable \"{}\": {}", name, err)),
+    }
+}
+
+/// Expand `${ENV_VAR}`, `${ENV_VAR:-default}` and built-in variables `{pid}`, `{exe}`, `{hostname}` in configuration
+/// value. `$$`, `{{` and `}}` may be used to insert literal `$`, `{` and `}`.
+pub(crate) fn expand(value: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(pos) = rest.find(&['$', '{', '}'][..]) {
+        result.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        let position = value.len() - tail.len();
+
+        rest = if let Some(tail) = tail.strip_prefix("$$") {
+            result.push('$');
+            tail
+        } else if let Some(tail) = tail.strip_prefix("{{") {
+            result.push('{');
+            tail
+        } else if let Some(tail) = tail.strip_prefix("}}") {
+            result.push('}');
+            tail
+        } else if let Some(tail) = tail.strip_prefix("${") {
+            let end = tail.find('}')
+                .ok_or_else(|| format!("Unterminated \"${{\" at position {}", position))?;
+            result.push_str(&env_var(&tail[..end])?);
+            &tail[end + 1..]
+        } else if let Some(tail) = tail.strip_prefix('{') {
+            let end = tail.find('}')
+                .ok_or_else(|| format!("Unterminated \"{{\" at position {}", position))?;
+            result.push_str(&builtin(&tail[..end])?);
+            &tail[end + 1..]
+        } else if tail.starts_with('}') {
+            return Err(format!("Unmatched \"}}\" at position {}", position));
+        } else {
+            // Lone '$'.
+            result.push('$');
+            &tail[1..]
+        };
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+/// Make value which is not changed by `expand()`.
+pub(crate) fn escape(value: &str) -> String {
+    value.replace('$', "$$").replace('{', "{{").replace('}', "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std;
+
+    use super::expand;
+
+    #[test]
+    fn literals() {
+        assert_eq!(expand("plain").unwrap(), "plain");
+        assert_eq!(expand("$$ {{}} $").unwrap(), "$ {} $");
+    }
+
+    #[test]
+    fn env_vars() {
+        std::env::set_var("LOG4RS_SYSLOG_EXPAND_TEST", "value");
+        std::env::set_var("LOG4RS_SYSLOG_EXPAND_TEST_EMPTY", "");
+        std::env::remove_var("LOG4RS_SYSLOG_EXPAND_TEST_UNSET");
+
+        assert_eq!(expand("a-${LOG4RS_SYSLOG_EXPAND_TEST}-b").unwrap(), "a-value-b");
+        assert_eq!(expand("${LOG4RS_SYSLOG_EXPAND_TEST:-default}").unwrap(), "value");
+        assert_eq!(expand("${LOG4RS_SYSLOG_EXPAND_TEST_EMPTY:-default}").unwrap(), "default");
+        assert_eq!(expand("${LOG4RS_SYSLOG_EXPAND_TEST_UNSET:-default}").unwrap(), "default");
+        assert!(expand("${LOG4RS_SYSLOG_EXPAND_TEST_UNSET}").is_err());
+        assert!(expand("${LOG4RS_SYSLOG_EXPAND_TEST").is_err());
+    }
+
+    #[test]
+    fn builtins() {
+        assert_eq!(expand("{pid}").unwrap(), std::process::id().to_string());
+        assert!(!expand("{hostname}").unwrap().is_empty());
+        assert!(!expand("{exe}").unwrap().is_empty());
+        assert!(expand("{unknown}").is_err());
+        assert!(expand("}").is_err());
+    }
+}
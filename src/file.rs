@@ -2,38 +2,243 @@
 
 use std;
 
-use libc;
+use error;
+use expand;
+use ident;
 use log;
 use log4rs;
+use sd_daemon;
+use serde;
 use syslog;
 
-#[derive(Deserialize)]
-struct SyslogAppenderOpenlogConfig {
-    ident: String,
-    option: syslog::LogOption,
-    facility: syslog::Facility,
+/// The `openlog` section of `libc-syslog` appender configuration.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SyslogAppenderOpenlogConfig {
+    pub(crate) ident: String,
+    pub(crate) option: syslog::LogOption,
+    pub(crate) facility: syslog::Facility,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mask: Option<syslog::LogMask>,
 }
 
-#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
-#[allow(non_camel_case_types)]
-enum FakeLibcLogLevel {
-    LOG_EMERG,
-    LOG_ALERT,
-    LOG_CRIT,
-    LOG_ERR,
-    LOG_WARNING,
-    LOG_NOTICE,
-    LOG_INFO,
-    LOG_DEBUG,
+/// Map of log levels to severities. Unlike `BTreeMap`, fails on unknown and duplicate levels.
+#[derive(Debug)]
+pub(crate) struct LevelMapConf(pub(crate) std::collections::BTreeMap<log::Level, syslog::Severity>);
+
+struct LevelMapConfVisitor;
+
+impl<'de> serde::de::Visitor<'de> for LevelMapConfVisitor {
+    type Value = LevelMapConf;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("map of log levels to syslog severities")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        use serde::de::Error;
+
+        let mut map = std::collections::BTreeMap::new();
+        while let Some(str_level) = access.next_key::<String>()? {
+            let level = str_level.trim().parse::<log::Level>().map_err(|_| {
+                A::Error::custom(format!(
+                    "Unknown log level in level_map: \"{}\", expected one of: Error, Warn, Info, Debug, Trace",
+                    str_level
+                ))
+            })?;
+            let severity = access.next_value()?;
+
+            if map.insert(level, severity).is_some() {
+                return Err(A::Error::custom(format!(
+                    "Duplicate log level in level_map: \"{}\"",
+                    str_level
+                )));
+            }
+        }
+
+        Ok(LevelMapConf(map))
+    }
 }
 
-type LevelMapConf = std::collections::BTreeMap<log::Level, FakeLibcLogLevel>;
+impl LevelMapConf {
+    /// Levels missing in map use default mapping.
+    fn into_level_map(self) -> Box<syslog::LevelMap> {
+        let mut map = std::collections::BTreeMap::new();
+        for level in &[
+            log::Level::Error,
+            log::Level::Warn,
+            log::Level::Info,
+            log::Level::Debug,
+            log::Level::Trace,
+        ] {
+            let libc_level = match self.0.get(level) {
+                Some(&severity) => severity.into(),
+                None => syslog::default_level_map(*level),
+            };
+            let _ = map.insert(*level, libc_level);
+        }
 
-#[derive(Deserialize)]
-struct SyslogAppenderConfig {
-    openlog: Option<SyslogAppenderOpenlogConfig>,
-    encoder: Option<log4rs::encode::EncoderConfig>,
-    level_map: Option<LevelMapConf>,
+        Box::new(move |l| map[&l])
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for LevelMapConf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(LevelMapConfVisitor)
+    }
+}
+
+impl serde::ser::Serialize for LevelMapConf {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+#[derive(Serialize)]
+struct EncoderConfigRef<'a, T: 'a> {
+    kind: &'a str,
+    #[serde(flatten)]
+    config: &'a T,
+}
+
+fn serialize_encoder_config<S>(
+    encoder_config: &Option<log4rs::encode::EncoderConfig>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::ser::Serializer,
+{
+    use serde::ser::Serialize;
+
+    encoder_config
+        .as_ref()
+        .map(|encoder_config| EncoderConfigRef {
+            kind: &encoder_config.kind,
+            config: &encoder_config.config,
+        })
+        .serialize(serializer)
+}
+
+/// Configuration of `libc-syslog`, `auto-syslog` and `kmsg` appenders, as in log4rs configuration file.
+#[derive(Deserialize, Serialize)]
+pub struct SyslogAppenderConfig {
+    /// Filled only by `SyslogAppender::config()`, log4rs passes `kind` to deserializers separately.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) path: Option<std::path::PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) openlog: Option<SyslogAppenderOpenlogConfig>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_encoder_config")]
+    pub(crate) encoder: Option<log4rs::encode::EncoderConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) level_map: Option<LevelMapConf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mask: Option<syslog::LogMask>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sanitize: Option<syslog::Sanitize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) socket_paths: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) announce: Option<bool>,
+}
+
+impl std::fmt::Debug for SyslogAppenderConfig {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("SyslogAppenderConfig")
+            .field("kind", &self.kind)
+            .field("path", &self.path)
+            .field("openlog", &self.openlog)
+            .field(
+                "encoder",
+                &self.encoder.as_ref().map(|encoder_config| &encoder_config.kind),
+            )
+            .field("level_map", &self.level_map)
+            .field("mask", &self.mask)
+            .field("sanitize", &self.sanitize)
+            .field("socket_paths", &self.socket_paths)
+            .field("announce", &self.announce)
+            .finish()
+    }
+}
+
+fn build_appender(
+    mut builder: syslog::SyslogAppenderBuilder,
+    config: SyslogAppenderConfig,
+    deserializers: &log4rs::file::Deserializers,
+) -> Result<Box<log4rs::append::Append>, Box<std::error::Error + Sync + Send>> {
+    if let Some(openlog_conf) = config.openlog {
+        let ident = if openlog_conf.ident == "auto" {
+            ident::auto_ident(None)
+        } else {
+            expand::expand(&openlog_conf.ident).map_err(|reason| error::SyslogError::InvalidConfig {
+                field: String::from("openlog.ident"),
+                reason,
+            })?
+        };
+        builder = builder.openlog(
+            &ident,
+            openlog_conf.option,
+            openlog_conf.facility,
+        );
+
+        if let Some(mask) = openlog_conf.mask {
+            if config.mask.is_some() {
+                return Err(Box::new(error::SyslogError::InvalidConfig {
+                    field: String::from("mask"),
+                    reason: String::from("Conflicts with openlog.mask"),
+                }));
+            }
+            builder = builder.log_mask(mask);
+        }
+    };
+
+    if let Some(mask) = config.mask {
+        builder = builder.log_mask(mask);
+    }
+
+    if let Some(encoder_conf) = config.encoder {
+        let encoder = deserializers
+            .deserialize(&encoder_conf.kind, encoder_conf.config.clone())
+            .map_err(|err| error::SyslogError::InvalidConfig {
+                field: String::from("encoder"),
+                reason: err.to_string(),
+            })?;
+        builder = builder.encoder(encoder).encoder_config(encoder_conf);
+    }
+
+    if let Some(level_map) = config.level_map {
+        builder = builder.level_map(level_map.into_level_map());
+    }
+
+    if let Some(sanitize) = config.sanitize {
+        builder = builder.sanitize(sanitize);
+    }
+
+    if let Some(announce) = config.announce {
+        builder = builder.announce(announce);
+    }
+
+    Ok(Box::new(builder.try_build()?))
+}
+
+fn reject_path(config: &SyslogAppenderConfig) -> Result<(), error::SyslogError> {
+    match config.path {
+        Some(_) => Err(error::SyslogError::InvalidConfig {
+            field: String::from("path"),
+            reason: String::from("Supported only by kmsg appender"),
+        }),
+        None => Ok(()),
+    }
 }
 
 struct SyslogAppenderDeserializer;
@@ -47,56 +252,145 @@ impl log4rs::file::Deserialize for SyslogAppenderDeserializer {
         config: Self::Config,
         deserializers: &log4rs::file::Deserializers,
     ) -> Result<Box<Self::Trait>, Box<std::error::Error + Sync + Send>> {
+        reject_path(&config)?;
+
         let mut builder = syslog::SyslogAppender::builder();
+        if let Some(ref socket_paths) = config.socket_paths {
+            builder = builder.socket_paths(&socket_paths[..]);
+        }
+        build_appender(builder, config, deserializers)
+    }
+}
+
+struct AutoSyslogAppenderDeserializer;
+
+impl log4rs::file::Deserialize for AutoSyslogAppenderDeserializer {
+    type Trait = log4rs::append::Append;
+    type Config = SyslogAppenderConfig;
+
+    fn deserialize(
+        &self,
+        config: Self::Config,
+        deserializers: &log4rs::file::Deserializers,
+    ) -> Result<Box<Self::Trait>, Box<std::error::Error + Sync + Send>> {
+        reject_path(&config)?;
 
-        if let Some(openlog_conf) = config.openlog {
-            builder = builder.openlog(
-                &openlog_conf.ident,
-                openlog_conf.option,
-                openlog_conf.facility,
-            );
+        let builder = match config.socket_paths {
+            Some(ref socket_paths) => syslog::SyslogAppender::auto_with_socket_paths(&socket_paths[..]),
+            None => syslog::SyslogAppender::auto(),
         };
+        build_appender(builder, config, deserializers)
+    }
+}
 
-        if let Some(encoder_conf) = config.encoder {
-            builder = builder.encoder(deserializers.deserialize(&encoder_conf.kind, encoder_conf.config)?);
+struct KmsgAppenderDeserializer;
+
+impl log4rs::file::Deserialize for KmsgAppenderDeserializer {
+    type Trait = log4rs::append::Append;
+    type Config = SyslogAppenderConfig;
+
+    fn deserialize(
+        &self,
+        config: Self::Config,
+        deserializers: &log4rs::file::Deserializers,
+    ) -> Result<Box<Self::Trait>, Box<std::error::Error + Sync + Send>> {
+        if config.socket_paths.is_some() {
+            return Err(Box::new(error::SyslogError::InvalidConfig {
+                field: String::from("socket_paths"),
+                reason: String::from("Not supported by kmsg appender"),
+            }));
         }
 
-        if let Some(level_map) = config.level_map {
-            let mut map = std::collections::BTreeMap::new();
-            for (level, libc_level) in level_map {
-                let libc_level = match libc_level {
-                    FakeLibcLogLevel::LOG_EMERG => libc::LOG_EMERG,
-                    FakeLibcLogLevel::LOG_ALERT => libc::LOG_ALERT,
-                    FakeLibcLogLevel::LOG_CRIT => libc::LOG_CRIT,
-                    FakeLibcLogLevel::LOG_ERR => libc::LOG_ERR,
-                    FakeLibcLogLevel::LOG_WARNING => libc::LOG_WARNING,
-                    FakeLibcLogLevel::LOG_NOTICE => libc::LOG_NOTICE,
-                    FakeLibcLogLevel::LOG_INFO => libc::LOG_INFO,
-                    FakeLibcLogLevel::LOG_DEBUG => libc::LOG_DEBUG,
-                };
-                let _ = map.insert(level, libc_level);
-            }
+        let builder = match config.path {
+            Some(ref path) => syslog::SyslogAppender::kmsg_with_path(path),
+            None => syslog::SyslogAppender::kmsg(),
+        };
+        build_appender(builder, config, deserializers)
+    }
+}
 
-            for level in &[
-                log::Level::Error,
-                log::Level::Warn,
-                log::Level::Info,
-                log::Level::Debug,
-                log::Level::Trace,
-            ] {
-                let _ = map.get(level)
-                    .ok_or_else(|| format!("Log level missing in map: {:?}", level))?;
-            }
+/// Configuration of `sd-daemon` encoder, as in log4rs configuration file.
+#[derive(Deserialize)]
+pub struct SdDaemonEncoderConfig {
+    encoder: Option<log4rs::encode::EncoderConfig>,
+    level_map: Option<LevelMapConf>,
+    facility: Option<syslog::Facility>,
+}
 
-            builder = builder.level_map(Box::new(move |l| map[&l]));
-        }
+impl std::fmt::Debug for SdDaemonEncoderConfig {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("SdDaemonEncoderConfig")
+            .field(
+                "encoder",
+                &self.encoder.as_ref().map(|encoder_config| &encoder_config.kind),
+            )
+            .field("level_map", &self.level_map)
+            .field("facility", &self.facility)
+            .finish()
+    }
+}
+
+struct SdDaemonEncoderDeserializer;
 
-        Ok(Box::new(builder.build()))
+impl log4rs::file::Deserialize for SdDaemonEncoderDeserializer {
+    type Trait = log4rs::encode::Encode;
+    type Config = SdDaemonEncoderConfig;
+
+    fn deserialize(
+        &self,
+        config: Self::Config,
+        deserializers: &log4rs::file::Deserializers,
+    ) -> Result<Box<Self::Trait>, Box<std::error::Error + Sync + Send>> {
+        let inner: Box<log4rs::encode::Encode> = match config.encoder {
+            Some(encoder_conf) => deserializers
+                .deserialize(&encoder_conf.kind, encoder_conf.config)
+                .map_err(|err| error::SyslogError::InvalidConfig {
+                    field: String::from("encoder"),
+                    reason: err.to_string(),
+                })?,
+            None => Box::new(log4rs::encode::pattern::PatternEncoder::default()),
+        };
+
+        let mut encoder = sd_daemon::SdDaemonEncoder::new(inner);
+        if let Some(level_map) = config.level_map {
+            encoder = encoder.level_map(level_map.into_level_map());
+        }
+        if let Some(facility) = config.facility {
+            encoder = encoder.facility(facility);
+        }
+        Ok(Box::new(encoder))
     }
 }
 
+/// JSON Schema (draft-07) of `libc-syslog`, `auto-syslog` and `kmsg` appender configuration, including `sd-daemon`
+/// encoder.
+///
+/// May be used to validate log4rs configuration files without starting the application. The same schema is shipped
+/// as `schema/libc-syslog.schema.json` in the source package.
+pub fn config_schema() -> &'static str {
+    include_str!("../schema/libc-syslog.schema.json")
+}
+
 /// Register deserializer for creating syslog appender based on log4rs configuration file.
 ///
+/// Levels not listed in `level_map` use the default mapping: `Error` to `LOG_ERR`, `Warn` to `LOG_WARNING`, `Info`
+/// to `LOG_INFO`, `Debug` and `Trace` to `LOG_DEBUG`.
+///
+/// `openlog.ident` in configuration may contain references to environment variables (`${VAR}` or
+/// `${VAR:-default}`) and built-in variables `{pid}`, `{exe}` and `{hostname}`. Use `$$`, `{{` and `}}` to get
+/// literal `$`, `{` and `}`. Special value `auto` derives ident from the name of the running program, see
+/// `auto_ident()`.
+///
+/// `socket_paths` is an ordered list of local syslog sockets, see `SyslogAppenderBuilder::socket_paths()`. Names
+/// starting with `@` are Linux abstract-namespace names. `announce` enables logging of the chosen backend, see
+/// `SyslogAppenderBuilder::announce()`. Top-level `mask` filters messages of appender without `openlog` section, like
+/// `openlog.mask`.
+///
+/// Also registers `auto-syslog` appender, which accepts the same configuration as `libc-syslog` and chooses backend
+/// like `SyslogAppender::auto()`, `kmsg` appender, which writes into kernel log with `KmsgBackend` (optional `path`
+/// sets kernel log device, see `SyslogAppender::kmsg_with_path()`), and `sd-daemon` encoder, see `SdDaemonEncoder`.
+///
 /// See `./examples/from_conf.rs` for full example.
 ///
 /// # Examples
@@ -111,4 +405,7 @@ impl log4rs::file::Deserialize for SyslogAppenderDeserializer {
 /// ```
 pub fn register(deserializers: &mut log4rs::file::Deserializers) {
     deserializers.insert("libc-syslog", SyslogAppenderDeserializer);
+    deserializers.insert("auto-syslog", AutoSyslogAppenderDeserializer);
+    deserializers.insert("kmsg", KmsgAppenderDeserializer);
+    deserializers.insert("sd-daemon", SdDaemonEncoderDeserializer);
 }
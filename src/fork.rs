@@ -0,0 +1,97 @@
+use std;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use libc;
+use syslog;
+
+// Zero means "not cached yet", real PIDs are always positive.
+static CACHED_PID: AtomicUsize = AtomicUsize::new(0);
+
+type ForkGuard = (
+    std::sync::MutexGuard<'static, syslog::IdentHolder>,
+    std::sync::MutexGuard<'static, syslog::AppenderRegistry>,
+);
+
+thread_local! {
+    // Held by the forking thread between `prepare` and `parent`/`child` handlers of pthread_atfork().
+    static FORK_GUARD: std::cell::RefCell<Option<ForkGuard>> = std::cell::RefCell::new(None);
+}
+
+/// PID of the current process. Cached and refreshed after `fork()`.
+pub(crate) fn pid() -> libc::pid_t {
+    match CACHED_PID.load(Ordering::Relaxed) {
+        0 => refresh_pid(),
+        pid => pid as libc::pid_t,
+    }
+}
+
+fn refresh_pid() -> libc::pid_t {
+    // getpid() is async-signal-safe, so this may be called from the pthread_atfork() child handler.
+    let pid = unsafe { libc::getpid() };
+    CACHED_PID.store(pid as usize, Ordering::Relaxed);
+    pid
+}
+
+/// Restore state of syslog appenders in the child process after `fork()`.
+///
+/// Should be called in the child as soon as possible, before any logging. Reopens the connection to the system logger
+/// using arguments of the last openlog() call, lets backends of activated appenders replace connections shared with
+/// the parent (see `SyslogBackend::after_fork()`) and refreshes the cached PID. Not needed when the child immediately
+/// calls `exec()`.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate libc;
+/// extern crate log4rs_syslog;
+///
+/// match unsafe { libc::fork() } {
+///     0 => log4rs_syslog::after_fork(),
+///     -1 => panic!("fork() failed"),
+///     _ => {},
+/// }
+/// ```
+pub fn after_fork() {
+    let _ = refresh_pid();
+    syslog::reset_persistent_buf();
+    syslog::after_fork_backends();
+    syslog::lock_ident_holder().reopen();
+}
+
+unsafe extern "C" fn atfork_prepare() {
+    // Prevent other threads from holding the locks at the moment of fork: they would never be released in the child.
+    // Other threads hold them only briefly and never while calling back into user code.
+    let guard = (syslog::lock_ident_holder(), syslog::lock_appenders());
+    FORK_GUARD.with(|fork_guard| *fork_guard.borrow_mut() = Some(guard));
+}
+
+unsafe extern "C" fn atfork_parent() {
+    FORK_GUARD.with(|fork_guard| *fork_guard.borrow_mut() = None);
+}
+
+unsafe extern "C" fn atfork_child() {
+    let _ = refresh_pid();
+    FORK_GUARD.with(|fork_guard| *fork_guard.borrow_mut() = None);
+}
+
+/// Register `pthread_atfork()` handlers which keep the appender state consistent across `fork()`.
+///
+/// Handlers only do what is safe to do in the child of a multithreaded process: they make sure that internal locks
+/// are not held by other threads during fork and refresh the cached PID. Reopening of connections still requires a
+/// call to `after_fork()` in the child. Handlers are registered only once, subsequent calls do nothing.
+///
+/// The `prepare` handler waits until other threads release internal locks of this crate. So `fork()` must not be
+/// called while the forking thread itself may hold them: from a signal handler which may interrupt logging, or from
+/// a custom `SyslogBackend`, encoder or level map. Doing so deadlocks.
+pub fn install_fork_handlers() -> std::io::Result<()> {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+
+    let mut result = Ok(());
+    INSTALL.call_once(|| {
+        let err = unsafe { libc::pthread_atfork(Some(atfork_prepare), Some(atfork_parent), Some(atfork_child)) };
+        if err != 0 {
+            result = Err(std::io::Error::from_raw_os_error(err));
+        }
+    });
+    result
+}
@@ -0,0 +1,429 @@
+use std;
+use std::io::BufRead;
+
+use backend;
+use error;
+use ident;
+use libc;
+use socket;
+use syslog;
+
+/// Options of `forward_child()`.
+pub struct ForwardOptions {
+    ident: Option<String>,
+    facility: syslog::Facility,
+    stdout_severity: syslog::Severity,
+    stderr_severity: syslog::Severity,
+    sd_daemon_prefix: bool,
+    sanitize: syslog::Sanitize,
+    socket_paths: Option<Vec<String>>,
+    backend: Option<Box<backend::SyslogBackend>>,
+}
+
+impl std::fmt::Debug for ForwardOptions {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "ForwardOptions {{ident: {:?}, facility: {:?}, stdout_severity: {:?}, stderr_severity: {:?}, \
+             sd_daemon_prefix: {:?}, sanitize: {:?}, socket_paths: {:?}, backend: {}}}",
+            self.ident,
+            self.facility,
+            self.stdout_severity,
+            self.stderr_severity,
+            self.sd_daemon_prefix,
+            self.sanitize,
+            self.socket_paths,
+            match self.backend {
+                Some(_) => "Some(_)",
+                None => "None",
+            }
+        )
+    }
+}
+
+impl Default for ForwardOptions {
+    fn default() -> Self {
+        Self {
+            ident: None,
+            facility: syslog::Facility::User,
+            stdout_severity: syslog::Severity::Info,
+            stderr_severity: syslog::Severity::Err,
+            sd_daemon_prefix: false,
+            sanitize: syslog::Sanitize::Escape,
+            socket_paths: None,
+            backend: None,
+        }
+    }
+}
+
+impl ForwardOptions {
+    /// Create options with defaults: ident is the name of the child, facility is `Facility::User`, stdout lines are
+    /// sent with `Severity::Info`, stderr lines with `Severity::Err`, control characters are escaped and records are
+    /// sent to the first of `/dev/log`, `/var/run/syslog` and `/var/run/log` which accepts connection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set ident instead of the name of the child. Ident is validated by `forward_child()`.
+    pub fn ident(mut self, ident: &str) -> Self {
+        self.ident = Some(String::from(ident));
+        self
+    }
+
+    /// Set facility. `Facility::Kern` is rejected by `forward_child()`.
+    pub fn facility(mut self, facility: syslog::Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Set severity of lines read from stdout.
+    pub fn stdout_severity(mut self, severity: syslog::Severity) -> Self {
+        self.stdout_severity = severity;
+        self
+    }
+
+    /// Set severity of lines read from stderr.
+    pub fn stderr_severity(mut self, severity: syslog::Severity) -> Self {
+        self.stderr_severity = severity;
+        self
+    }
+
+    /// Recognize `<N>` prefixes of sd-daemon(3), like `<3>` for `LOG_ERR`, and use them as severity of the line
+    /// instead of the per-stream one. Prefix is removed, facility part of priority is ignored.
+    pub fn sd_daemon_prefix(mut self, enabled: bool) -> Self {
+        self.sd_daemon_prefix = enabled;
+        self
+    }
+
+    /// Set the way control characters in lines are treated.
+    pub fn sanitize(mut self, sanitize: syslog::Sanitize) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Send records to the first local syslog socket from list which accepts connection, like
+    /// `SyslogAppenderBuilder::socket_paths()`. List is validated by `forward_child()`.
+    pub fn socket_paths<S: AsRef<str>>(mut self, paths: &[S]) -> Self {
+        self.socket_paths = Some(paths.iter().map(|path| String::from(path.as_ref())).collect());
+        self.backend = None;
+        self
+    }
+
+    /// Set backend. `libc`'s syslog() supports only one ident per process, so by default records are sent directly
+    /// to local syslog socket, see `socket_paths()`.
+    pub fn backend(mut self, backend: Box<backend::SyslogBackend>) -> Self {
+        self.backend = Some(backend);
+        self.socket_paths = None;
+        self
+    }
+}
+
+/// Split `<N>` prefix of sd-daemon(3) from line.
+pub(crate) fn parse_sd_prefix(line: &[u8]) -> Option<(syslog::Severity, &[u8])> {
+    if line.first() != Some(&b'<') {
+        return None;
+    }
+
+    let end = line.iter().take(5).position(|&c| c == b'>')?;
+    let digits = &line[1..end];
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let priority = std::str::from_utf8(digits).ok()?.parse::<libc::c_int>().ok()?;
+    if priority > 191 {
+        return None;
+    }
+
+    Some((syslog::Severity::from_libc(priority), &line[end + 1..]))
+}
+
+// Appender is created when the first line arrives. `Command::spawn()` returns after exec() in the child, but the kernel
+// may switch `/proc/PID/comm` to the name of the new program slightly later.
+enum Appender {
+    Pending {
+        ident: Option<String>,
+        facility: syslog::Facility,
+        sanitize: syslog::Sanitize,
+        backend: Box<backend::SyslogBackend>,
+    },
+    Ready(syslog::SyslogAppender),
+}
+
+struct Forwarder {
+    pid: u32,
+    sd_daemon_prefix: bool,
+    appender: std::sync::Mutex<Option<Appender>>,
+}
+
+impl Forwarder {
+    fn send(&self, severity: syslog::Severity, message: &[u8]) -> Result<(), error::SyslogError> {
+        let mut appender = self.appender
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        *appender = match appender.take() {
+            Some(Appender::Pending {
+                ident,
+                facility,
+                sanitize,
+                backend,
+            }) => {
+                let ident = ident
+                    .or_else(|| ident::process_ident(self.pid))
+                    .unwrap_or_else(|| format!("child-{}", self.pid));
+                Some(Appender::Ready(syslog::SyslogAppender::builder()
+                    .openlog(&ident, syslog::LogOption::empty(), facility)
+                    .sanitize(sanitize)
+                    .backend(backend)
+                    // `emergency_log()` should keep ident of the parent.
+                    .emergency(false)
+                    .build()))
+            },
+            ready => ready,
+        };
+
+        match *appender {
+            Some(Appender::Ready(ref appender)) => appender.append_raw(severity, message),
+            _ => Ok(()),
+        }
+    }
+
+    fn forward_lines<R: std::io::Read>(&self, stream: R, severity: syslog::Severity) {
+        let mut reader = std::io::BufReader::new(stream);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => (),
+            }
+
+            let line = syslog::trim_line_breaks(&line);
+            let (severity, message) = match parse_sd_prefix(line) {
+                Some(prefixed) if self.sd_daemon_prefix => prefixed,
+                _ => (severity, line),
+            };
+
+            // There is nobody to report errors to, keep forwarding.
+            let _ = self.send(severity, message);
+        }
+    }
+}
+
+/// Handle of threads started by `forward_child()`.
+#[derive(Debug)]
+pub struct ForwardHandle {
+    threads: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl ForwardHandle {
+    /// Wait until the child closes its stdout and stderr, usually when it exits, and all lines are sent.
+    pub fn join(self) {
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Forward lines written by child process to its stdout and stderr into syslog.
+///
+/// Piped streams are taken from `child` and read line by line on background threads, each line is sent as a
+/// separate message with severity configured for the stream. At least one of stdout and stderr must be piped.
+/// Errors of sending are ignored. Threads stop when streams are closed, dropping `ForwardHandle` does not stop them.
+/// If a thread can not be started, streams are left in `child`.
+///
+/// Name of the child is read when the first line arrives, from `/proc/PID/comm` (Linux only), so the child should not
+/// be reaped by `wait()` before that. If the name is not available, ident is `child-PID`.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate log4rs_syslog;
+///
+/// use std::process::{Command, Stdio};
+///
+/// let mut child = Command::new("helper")
+///     .stdout(Stdio::piped())
+///     .stderr(Stdio::piped())
+///     .spawn()
+///     .unwrap();
+///
+/// let forwarding = log4rs_syslog::forward_child(
+///     &mut child,
+///     log4rs_syslog::ForwardOptions::new()
+///         .facility(log4rs_syslog::Facility::Daemon)
+///         .sd_daemon_prefix(true),
+/// ).unwrap();
+///
+/// forwarding.join();
+/// child.wait().unwrap();
+/// ```
+pub fn forward_child(
+    child: &mut std::process::Child,
+    options: ForwardOptions,
+) -> Result<ForwardHandle, error::SyslogError> {
+    if child.stdout.is_none() && child.stderr.is_none() {
+        return Err(error::SyslogError::InvalidConfig {
+            field: String::from("stdio"),
+            reason: String::from("neither stdout nor stderr of child is piped"),
+        });
+    }
+
+    if let Some(ref ident) = options.ident {
+        syslog::validate_ident(ident)?;
+    }
+    syslog::validate_facility("facility", options.facility)?;
+
+    let backend = match (options.backend, options.socket_paths) {
+        (Some(backend), _) => backend,
+        (None, Some(socket_paths)) => {
+            syslog::validate_socket_paths(&socket_paths)?;
+            Box::new(socket::SocketBackend::unix_candidates(&socket_paths))
+        },
+        (None, None) => Box::new(socket::SocketBackend::unix_candidates(socket::DEFAULT_SOCKET_PATHS)),
+    };
+
+    let forwarder = std::sync::Arc::new(Forwarder {
+        pid: child.id(),
+        sd_daemon_prefix: options.sd_daemon_prefix,
+        appender: std::sync::Mutex::new(Some(Appender::Pending {
+            ident: options.ident,
+            facility: options.facility,
+            sanitize: options.sanitize,
+            backend,
+        })),
+    });
+
+    forward_streams(child, forwarder, options.stdout_severity, options.stderr_severity, |name, f| {
+        std::thread::Builder::new().name(String::from(name)).spawn(f)
+    })
+}
+
+type Stream = Box<std::io::Read + Send>;
+
+// Threads are started before streams are taken from `child`: if one of threads can not be started, streams stay in
+// `child` and threads which are already running are stopped.
+fn forward_streams<F>(
+    child: &mut std::process::Child,
+    forwarder: std::sync::Arc<Forwarder>,
+    stdout_severity: syslog::Severity,
+    stderr_severity: syslog::Severity,
+    mut spawn: F,
+) -> Result<ForwardHandle, error::SyslogError>
+where
+    F: FnMut(&str, Box<FnOnce() + Send>) -> std::io::Result<std::thread::JoinHandle<()>>,
+{
+    let streams = [
+        ("forward-stdout", child.stdout.is_some(), stdout_severity),
+        ("forward-stderr", child.stderr.is_some(), stderr_severity),
+    ];
+
+    let mut threads = Vec::new();
+    let mut senders = Vec::new();
+    for &(name, piped, severity) in &streams {
+        if !piped {
+            senders.push(None);
+            continue;
+        }
+
+        let (sender, receiver) = std::sync::mpsc::channel::<Stream>();
+        let forwarder = forwarder.clone();
+        let thread = spawn(
+            name,
+            Box::new(move || {
+                // Sender is dropped without sending on error.
+                if let Ok(stream) = receiver.recv() {
+                    forwarder.forward_lines(stream, severity);
+                }
+            }),
+        );
+
+        match thread {
+            Ok(thread) => {
+                threads.push(thread);
+                senders.push(Some(sender));
+            },
+            Err(err) => {
+                drop(senders);
+                ForwardHandle { threads }.join();
+                return Err(err.into());
+            },
+        }
+    }
+
+    // Threads wait for streams, so sending does not fail.
+    if let (Some(ref sender), Some(stdout)) = (&senders[0], child.stdout.take()) {
+        let _ = sender.send(Box::new(stdout));
+    }
+    if let (Some(ref sender), Some(stderr)) = (&senders[1], child.stderr.take()) {
+        let _ = sender.send(Box::new(stderr));
+    }
+
+    Ok(ForwardHandle { threads })
+}
+
+#[cfg(test)]
+mod tests {
+    use std;
+    use std::process::{Command, Stdio};
+
+    use kmsg;
+    use syslog;
+
+    use super::{forward_streams, Appender, Forwarder};
+
+    #[test]
+    fn spawn_error() {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("echo out; echo err >&2")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let forwarder = std::sync::Arc::new(Forwarder {
+            pid: child.id(),
+            sd_daemon_prefix: false,
+            appender: std::sync::Mutex::new(Some(Appender::Pending {
+                ident: None,
+                facility: syslog::Facility::User,
+                sanitize: syslog::Sanitize::Escape,
+                // Nothing is sent.
+                backend: Box::new(kmsg::KmsgBackend::with_path("/nonexistent")),
+            })),
+        });
+
+        // The first thread starts, the second one fails.
+        let finished = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut spawned = 0;
+        let err = forward_streams(
+            &mut child,
+            forwarder.clone(),
+            syslog::Severity::Info,
+            syslog::Severity::Err,
+            |_, f| {
+                spawned += 1;
+                if spawned > 1 {
+                    return Err(std::io::Error::new(std::io::ErrorKind::Other, "no threads"));
+                }
+
+                let finished = finished.clone();
+                Ok(std::thread::spawn(move || {
+                    f();
+                    finished.store(true, std::sync::atomic::Ordering::SeqCst);
+                }))
+            },
+        ).unwrap_err();
+        assert!(err.to_string().contains("no threads"), "{}", err);
+
+        // The first thread is joined and does not hold forwarder, both streams are left to the caller.
+        assert!(finished.load(std::sync::atomic::Ordering::SeqCst));
+        assert_eq!(std::sync::Arc::strong_count(&forwarder), 1);
+        assert!(child.stdout.is_some());
+        assert!(child.stderr.is_some());
+        assert!(child.wait().unwrap().success());
+    }
+}
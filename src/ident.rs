@@ -0,0 +1,99 @@
+use std;
+
+use libc;
+
+/// Maximum length of APP-NAME in RFC 5424.
+pub(crate) const MAX_IDENT_LEN: usize = 48;
+
+/// Make string usable as ident: replace characters not allowed in APP-NAME by RFC 5424 and limit the length.
+fn sanitize(name: &str) -> Option<String> {
+    let ident: String = name.trim()
+        .chars()
+        .map(|c| if c > ' ' && c < '\x7f' { c } else { '_' })
+        .take(MAX_IDENT_LEN)
+        .collect();
+
+    if ident.is_empty() {
+        None
+    } else {
+        Some(ident)
+    }
+}
+
+/// Get name of the host, as returned by gethostname().
+pub(crate) fn hostname() -> std::io::Result<String> {
+    let mut buf = [0u8; 256];
+    let res = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if res != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    Ok(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+fn from_comm() -> Option<String> {
+    std::fs::read_to_string("/proc/self/comm")
+        .ok()
+        .and_then(|comm| sanitize(&comm))
+}
+
+/// Ident of another process, from `/proc/PID/comm` (Linux only).
+pub(crate) fn process_ident(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .and_then(|comm| sanitize(&comm))
+}
+
+fn from_argv0() -> Option<String> {
+    std::env::args_os().next().and_then(|argv0| {
+        std::path::Path::new(&argv0)
+            .file_name()
+            .and_then(|name| sanitize(&name.to_string_lossy()))
+    })
+}
+
+/// Derive ident from the name of the running program.
+///
+/// Sources are tried in the following order:
+///
+/// 1. `/proc/self/comm` (Linux only; the same name as shown by `ps`, truncated by kernel to 15 characters).
+/// 2. File name from `argv[0]`.
+/// 3. `package_name`, usually the cargo package name captured by the `auto_ident!()` macro.
+///
+/// Characters not allowed in APP-NAME by RFC 5424 are replaced by `_`, length is limited to 48 characters. Returns
+/// `"unknown"` if everything fails.
+pub fn auto_ident(package_name: Option<&str>) -> String {
+    from_comm()
+        .or_else(from_argv0)
+        .or_else(|| package_name.and_then(sanitize))
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Derive ident from the name of the running program, falling back to the name of the cargo package of the calling
+/// crate.
+///
+/// See `auto_ident()` for details.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate log4rs_syslog;
+///
+/// # fn main() {
+/// let appender = log4rs_syslog::SyslogAppender::builder()
+///     .openlog(
+///         &auto_ident!(),
+///         log4rs_syslog::LogOption::LOG_PID,
+///         log4rs_syslog::Facility::Daemon,
+///     )
+///     .build();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! auto_ident {
+    () => {
+        $crate::auto_ident(Some(env!("CARGO_PKG_NAME")))
+    };
+}
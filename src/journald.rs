@@ -0,0 +1,162 @@
+#![forbid(unsafe_code)]
+
+use std;
+
+use backend;
+use error;
+use ident;
+use libc;
+use socket;
+use syslog;
+
+/// Path of journald's socket for the native protocol.
+pub(crate) const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+struct State {
+    ident: String,
+    socket: Option<std::os::unix::net::UnixDatagram>,
+    record: Vec<u8>,
+}
+
+/// Backend which sends records to journald using its native protocol, so that priority, facility and ident are
+/// stored as separate fields and multiline messages are kept intact.
+///
+/// Records are sent as single datagrams, messages which do not fit fail with `SyslogError::MessageTooLarge`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// let appender = log4rs_syslog::SyslogAppender::builder()
+///     .openlog("my-daemon", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+///     .backend(Box::new(log4rs_syslog::JournaldBackend::new()))
+///     .build();
+/// ```
+pub struct JournaldBackend {
+    path: std::path::PathBuf,
+    state: std::sync::Mutex<State>,
+}
+
+impl std::fmt::Debug for JournaldBackend {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "JournaldBackend {{path: {:?}}}", self.path)
+    }
+}
+
+impl Default for JournaldBackend {
+    fn default() -> Self {
+        Self::with_path(JOURNALD_SOCKET)
+    }
+}
+
+impl JournaldBackend {
+    /// Send records to `/run/systemd/journal/socket`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send records to socket with given path.
+    pub fn with_path<P: AsRef<std::path::Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            state: std::sync::Mutex::new(State {
+                ident: String::new(),
+                socket: None,
+                record: Vec::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn connect(&self) -> std::io::Result<std::os::unix::net::UnixDatagram> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        socket.connect(&self.path)?;
+        Ok(socket)
+    }
+}
+
+// Values with line breaks use binary format: name, line feed, little-endian 64-bit length, value, line feed.
+fn push_field(record: &mut Vec<u8>, name: &str, value: &[u8]) {
+    record.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        record.push(b'\n');
+        record.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        record.push(b'=');
+    }
+    record.extend_from_slice(value);
+    record.push(b'\n');
+}
+
+impl backend::SyslogBackend for JournaldBackend {
+    fn open(&self, ident: Option<&str>) -> Result<(), error::SyslogError> {
+        let mut state = self.lock();
+        state.ident = match ident {
+            Some(ident) => String::from(ident),
+            None => ident::auto_ident(None),
+        };
+        state.socket = Some(self.connect()?);
+        Ok(())
+    }
+
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), error::SyslogError> {
+        let mut state = self.lock();
+        let state = &mut *state;
+
+        let record = &mut state.record;
+        record.clear();
+        push_field(record, "PRIORITY", (priority & libc::LOG_PRIMASK).to_string().as_bytes());
+        let facility = priority & libc::LOG_FACMASK;
+        if facility != 0 {
+            push_field(record, "SYSLOG_FACILITY", (facility >> 3).to_string().as_bytes());
+        }
+        push_field(record, "SYSLOG_IDENTIFIER", state.ident.as_bytes());
+        push_field(record, "MESSAGE", syslog::trim_line_breaks(message));
+
+        if let Some(ref socket) = state.socket {
+            match socket.send(record) {
+                Ok(_) => return Ok(()),
+                Err(err) => {
+                    // Reconnecting does not help.
+                    if let err @ error::SyslogError::MessageTooLarge { .. } =
+                        socket::datagram_send_error(socket, record.len(), err)
+                    {
+                        return Err(err);
+                    }
+                },
+            }
+        }
+
+        // Journald may be restarted, try to reconnect once.
+        state.socket = None;
+        let socket = self.connect()?;
+        let _ = socket
+            .send(record)
+            .map_err(|err| socket::datagram_send_error(&socket, record.len(), err))?;
+        state.socket = Some(socket);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), error::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {
+        self.lock().socket = None;
+    }
+
+    fn after_fork(&self) {
+        // If connection fails, `send()` retries.
+        self.lock().socket = self.connect().ok();
+    }
+
+    fn destination(&self) -> Option<String> {
+        Some(self.path.display().to_string())
+    }
+}
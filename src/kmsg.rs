@@ -0,0 +1,182 @@
+#![forbid(unsafe_code)]
+
+use std;
+use std::io::Write;
+
+use backend;
+use error;
+use ident;
+use libc;
+use syslog;
+
+/// Path of kernel log device.
+pub(crate) const KMSG: &str = "/dev/kmsg";
+
+/// Maximum length of record accepted by kernel in a single write, including priority prefix (`LOG_LINE_MAX`).
+pub(crate) const MAX_RECORD_LEN: usize = 992;
+
+struct State {
+    ident: String,
+    file: Option<std::fs::File>,
+    record: Vec<u8>,
+}
+
+/// Backend which writes `<PRI>ident: message` records into kernel log, for tools running before any syslog daemon,
+/// like in initramfs.
+///
+/// Records longer than kernel allows (992 bytes) are truncated. Facility `LOG_KERN` is reserved for kernel, so
+/// `LOG_USER` is used when facility is not configured.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// let appender = log4rs_syslog::SyslogAppender::builder()
+///     .openlog("early-tool", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+///     .backend(Box::new(log4rs_syslog::KmsgBackend::new()))
+///     .build();
+/// ```
+pub struct KmsgBackend {
+    path: std::path::PathBuf,
+    state: std::sync::Mutex<State>,
+}
+
+impl std::fmt::Debug for KmsgBackend {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "KmsgBackend {{path: {:?}}}", self.path)
+    }
+}
+
+impl Default for KmsgBackend {
+    fn default() -> Self {
+        Self::with_path(KMSG)
+    }
+}
+
+impl KmsgBackend {
+    /// Write records into `/dev/kmsg`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write records into file with given path, like FIFO or regular file in tests. File is not created.
+    pub fn with_path<P: AsRef<std::path::Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            state: std::sync::Mutex::new(State {
+                ident: String::new(),
+                file: None,
+                record: Vec::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl syslog::SyslogAppender {
+    /// Create builder of appender which writes into `/dev/kmsg` with `KmsgBackend`.
+    ///
+    /// Unlike setting `KmsgBackend` through `SyslogAppenderBuilder::backend()`, configuration of such appender may
+    /// be exported with `SyslogAppender::config()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate log4rs_syslog;
+    ///
+    /// let appender = log4rs_syslog::SyslogAppender::kmsg()
+    ///     .openlog("early-tool", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+    ///     .build();
+    /// ```
+    pub fn kmsg() -> syslog::SyslogAppenderBuilder {
+        syslog::SyslogAppender::builder()
+            .config_kind(syslog::ConfigKind::Kmsg(None))
+            .builtin_backend(Box::new(KmsgBackend::new()))
+    }
+
+    /// Like `kmsg()`, but writes into file with given path, see `KmsgBackend::with_path()`.
+    pub fn kmsg_with_path<P: AsRef<std::path::Path>>(path: P) -> syslog::SyslogAppenderBuilder {
+        syslog::SyslogAppender::builder()
+            .config_kind(syslog::ConfigKind::Kmsg(Some(path.as_ref().to_path_buf())))
+            .builtin_backend(Box::new(KmsgBackend::with_path(path)))
+    }
+}
+
+// Do not cut UTF-8 sequence in the middle.
+fn truncate(record: &mut Vec<u8>, len: usize) {
+    if record.len() <= len {
+        return;
+    }
+
+    let mut len = len;
+    while len > 0 && (record[len] & 0xc0) == 0x80 {
+        len -= 1;
+    }
+    record.truncate(len);
+}
+
+impl backend::SyslogBackend for KmsgBackend {
+    fn open(&self, ident: Option<&str>) -> Result<(), error::SyslogError> {
+        let mut state = self.lock();
+        state.ident = match ident {
+            Some(ident) => String::from(ident),
+            None => ident::auto_ident(None),
+        };
+        state.file = Some(std::fs::OpenOptions::new().append(true).open(&self.path)?);
+        Ok(())
+    }
+
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), error::SyslogError> {
+        // Facility is not configured, `LOG_KERN` is rejected by `SyslogAppenderBuilder::try_build()`.
+        let priority = if priority & libc::LOG_FACMASK == 0 {
+            priority | libc::LOG_USER
+        } else {
+            priority
+        };
+
+        let mut state = self.lock();
+        let state = &mut *state;
+
+        let record = &mut state.record;
+        record.clear();
+        // Writing into `Vec` never fails.
+        let _ = write!(record, "<{}>{}: ", priority, state.ident);
+        record.extend_from_slice(syslog::trim_line_breaks(message));
+        truncate(record, MAX_RECORD_LEN);
+
+        match state.file {
+            // Every write() is a separate record.
+            Some(ref mut file) => {
+                let _ = file.write(record)?;
+                Ok(())
+            },
+            None => Err(error::SyslogError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "Kernel log is not opened",
+            ))),
+        }
+    }
+
+    fn flush(&self) -> Result<(), error::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {
+        self.lock().file = None;
+    }
+
+    fn after_fork(&self) {
+        let mut state = self.lock();
+        state.file = std::fs::OpenOptions::new().append(true).open(&self.path).ok();
+    }
+
+    fn destination(&self) -> Option<String> {
+        Some(self.path.display().to_string())
+    }
+}
@@ -37,6 +37,10 @@ extern crate serde_derive;
 #[cfg(feature = "unstable")]
 extern crate test; // For benchmark.
 
+#[cfg(target_family = "unix")]
+#[cfg(feature = "file")]
+mod expand;
+
 #[cfg(target_family = "unix")]
 #[cfg(feature = "file")]
 mod file;
@@ -44,7 +48,81 @@ mod file;
 #[cfg(feature = "file")]
 pub use file::*;
 
+#[cfg(target_family = "unix")]
+mod auto;
+#[cfg(target_family = "unix")]
+pub use auto::*;
+
+#[cfg(target_family = "unix")]
+mod backend;
+#[cfg(target_family = "unix")]
+pub use backend::*;
+
+#[cfg(target_family = "unix")]
+mod emergency;
+#[cfg(target_family = "unix")]
+pub use emergency::*;
+
+#[cfg(target_family = "unix")]
+mod error;
+#[cfg(target_family = "unix")]
+pub use error::*;
+
+#[cfg(target_family = "unix")]
+mod fork;
+#[cfg(target_family = "unix")]
+pub use fork::*;
+
+#[cfg(target_family = "unix")]
+mod forward;
+#[cfg(target_family = "unix")]
+pub use forward::*;
+
+#[cfg(target_family = "unix")]
+mod ident;
+#[cfg(target_family = "unix")]
+pub use ident::*;
+
+#[cfg(target_family = "unix")]
+mod panic_hook;
+#[cfg(target_family = "unix")]
+pub use panic_hook::*;
+
+#[cfg(target_family = "unix")]
+mod journald;
+#[cfg(target_family = "unix")]
+pub use journald::*;
+
+#[cfg(target_family = "unix")]
+mod kmsg;
+#[cfg(target_family = "unix")]
+pub use kmsg::*;
+
+#[cfg(target_family = "unix")]
+mod parse;
+#[cfg(target_family = "unix")]
+pub use parse::*;
+
+#[cfg(target_family = "unix")]
+mod receiver;
+#[cfg(target_family = "unix")]
+pub use receiver::*;
+
+#[cfg(target_family = "unix")]
+mod sd_daemon;
+#[cfg(target_family = "unix")]
+pub use sd_daemon::*;
+
+#[cfg(target_family = "unix")]
+mod socket;
+#[cfg(target_family = "unix")]
+pub use socket::*;
+
 #[cfg(target_family = "unix")]
 mod syslog;
 #[cfg(target_family = "unix")]
 pub use syslog::*;
+
+#[cfg(target_family = "unix")]
+#[cfg(feature = "testing")]
+pub mod testing;
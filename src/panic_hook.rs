@@ -0,0 +1,161 @@
+use std;
+
+use emergency;
+use error;
+use syslog;
+
+/// How to send multiline panic messages (with backtraces) to syslog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultilineMode {
+    /// Send every line as a separate record.
+    Split,
+    /// Send a single record with line breaks replaced by `\n` escape sequences.
+    Escape,
+}
+
+/// Configuration for `install_panic_hook_with()`.
+#[derive(Clone, Debug)]
+pub struct PanicHookConfig {
+    severity: syslog::Severity,
+    backtrace: bool,
+    multiline: MultilineMode,
+}
+
+impl Default for PanicHookConfig {
+    fn default() -> Self {
+        Self {
+            severity: syslog::Severity::Crit,
+            backtrace: false,
+            multiline: MultilineMode::Split,
+        }
+    }
+}
+
+impl PanicHookConfig {
+    /// Create default configuration: `Severity::Crit`, no backtrace, multiline messages are split.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set severity of panic messages.
+    pub fn severity(mut self, severity: syslog::Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Capture and send backtrace of the panicking thread.
+    pub fn backtrace(mut self, backtrace: bool) -> Self {
+        self.backtrace = backtrace;
+        self
+    }
+
+    /// Set how to send multiline messages.
+    pub fn multiline(mut self, multiline: MultilineMode) -> Self {
+        self.multiline = multiline;
+        self
+    }
+}
+
+// Falls back to `emergency_log()` if appender fails, so that the panic is not lost.
+fn send(appender: &syslog::SyslogAppender, severity: syslog::Severity, message: &str) {
+    if appender.append_raw(severity, message.as_bytes()).is_err() {
+        let _ = emergency::emergency_log(severity, message.as_bytes());
+    }
+}
+
+// `PanicInfo` is deprecated in favour of `PanicHookInfo`, which requires Rust 1.81.
+#[allow(deprecated)]
+fn format_message(info: &std::panic::PanicInfo, backtrace: bool) -> String {
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+
+    let payload = info.payload();
+    let payload = match payload.downcast_ref::<&str>() {
+        Some(payload) => *payload,
+        None => match payload.downcast_ref::<String>() {
+            Some(payload) => &payload[..],
+            None => "Box<Any>",
+        },
+    };
+
+    let mut message = match info.location() {
+        Some(location) => format!("thread '{}' panicked at '{}', {}", thread, payload, location),
+        None => format!("thread '{}' panicked at '{}'", thread, payload),
+    };
+
+    if backtrace {
+        message.push_str("\nstack backtrace:\n");
+        message.push_str(&std::backtrace::Backtrace::force_capture().to_string());
+    }
+
+    message
+}
+
+/// Install panic hook which sends panic messages into syslog through appender with given configuration, using the
+/// default `PanicHookConfig`.
+///
+/// Messages contain thread name, location and, optionally, backtrace. They are sent as is, without encoder and level
+/// map of the appender, but its backend, facility, log mask and sanitization are used. If appender fails to send a
+/// message, it is sent by `emergency_log()`. Previously installed hook (the default one prints to stderr) is called
+/// after sending the message.
+///
+/// Fails if appender configuration is not valid, see `SyslogAppenderBuilder::try_build()`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// log4rs_syslog::install_panic_hook(
+///     log4rs_syslog::SyslogAppender::builder().openlog(
+///         "my-daemon",
+///         log4rs_syslog::LogOption::LOG_PID,
+///         log4rs_syslog::Facility::Daemon,
+///     ),
+/// ).unwrap();
+/// ```
+pub fn install_panic_hook(appender_config: syslog::SyslogAppenderBuilder) -> Result<(), error::SyslogError> {
+    install_panic_hook_with(appender_config, PanicHookConfig::default())
+}
+
+/// Same as `install_panic_hook()`, with custom severity, backtraces and handling of multiline messages.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// log4rs_syslog::install_panic_hook_with(
+///     log4rs_syslog::SyslogAppender::builder(),
+///     log4rs_syslog::PanicHookConfig::new()
+///         .backtrace(true)
+///         .multiline(log4rs_syslog::MultilineMode::Escape),
+/// ).unwrap();
+/// ```
+pub fn install_panic_hook_with(
+    appender_config: syslog::SyslogAppenderBuilder,
+    config: PanicHookConfig,
+) -> Result<(), error::SyslogError> {
+    let appender = appender_config.try_build()?;
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let message = format_message(info, config.backtrace);
+
+        match config.multiline {
+            MultilineMode::Split => {
+                for line in message.lines().filter(|line| !line.trim().is_empty()) {
+                    send(&appender, config.severity, line);
+                }
+            },
+            MultilineMode::Escape => send(
+                &appender,
+                config.severity,
+                &message.trim_end().replace('\n', "\\n"),
+            ),
+        }
+
+        previous_hook(info);
+    }));
+    Ok(())
+}
@@ -0,0 +1,566 @@
+#![forbid(unsafe_code)]
+
+use std;
+
+use libc;
+use syslog;
+
+/// Element of RFC 5424 structured data: `[id name="value" ...]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuredElement {
+    /// SD-ID, like `timeQuality` or `example@32473`.
+    pub id: String,
+    /// Parameters in the original order, with escape sequences in values resolved.
+    pub params: Vec<(String, String)>,
+}
+
+/// Syslog message parsed by `parse_message()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyslogMessage {
+    /// Facility from PRI.
+    pub facility: syslog::Facility,
+    /// Severity from PRI.
+    pub severity: syslog::Severity,
+    /// Protocol version, `Some(1)` for RFC 5424 and `None` for RFC 3164.
+    pub version: Option<u32>,
+    /// Timestamp as it appears in the message.
+    pub timestamp: Option<String>,
+    /// Hostname, `None` if missing or NILVALUE.
+    pub hostname: Option<String>,
+    /// APP-NAME (TAG in RFC 3164).
+    pub app_name: Option<String>,
+    /// PROCID (PID in brackets after TAG in RFC 3164).
+    pub proc_id: Option<String>,
+    /// MSGID, RFC 5424 only.
+    pub msg_id: Option<String>,
+    /// Structured data, RFC 5424 only.
+    pub structured_data: Vec<StructuredElement>,
+    /// Message without trailing line breaks and NUL characters, and without UTF-8 BOM.
+    pub message: Vec<u8>,
+}
+
+impl SyslogMessage {
+    /// Message as text. Invalid UTF-8 sequences are replaced.
+    pub fn text(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.message)
+    }
+}
+
+/// Error returned by `parse_message()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Offset of the invalid part of message in bytes.
+    pub offset: usize,
+    /// What is wrong.
+    pub reason: &'static str,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{} at byte {}", self.reason, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const NIL: &str = "-";
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, reason: &'static str) -> Result<T, ParseError> {
+        Err(ParseError {
+            offset: self.pos,
+            reason,
+        })
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, c: u8, reason: &'static str) -> Result<(), ParseError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(reason)
+        }
+    }
+
+    /// Printable ASCII characters up to the next space or end of message.
+    fn token(&mut self, reason: &'static str) -> Result<&'a str, ParseError> {
+        let len = self.rest()
+            .iter()
+            .position(|&c| c == b' ')
+            .unwrap_or_else(|| self.rest().len());
+        let token = &self.rest()[..len];
+        if token.is_empty() {
+            return self.error(reason);
+        }
+        if let Some(bad) = token.iter().position(|&c| c <= b' ' || c >= 0x7f) {
+            self.pos += bad;
+            return self.error("Non-printable character");
+        }
+
+        self.pos += len;
+        // Only ASCII characters are allowed above.
+        Ok(std::str::from_utf8(token).unwrap())
+    }
+
+    fn nil_or_token(&mut self, reason: &'static str) -> Result<Option<String>, ParseError> {
+        let token = self.token(reason)?;
+        Ok(if token == NIL {
+            None
+        } else {
+            Some(String::from(token))
+        })
+    }
+
+    fn digits(&mut self, max_len: usize) -> Option<u32> {
+        let len = self.rest()
+            .iter()
+            .take(max_len)
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if len == 0 {
+            return None;
+        }
+
+        let value = std::str::from_utf8(&self.rest()[..len]).unwrap().parse().ok();
+        self.pos += len;
+        value
+    }
+
+    fn priority(&mut self) -> Result<(syslog::Facility, syslog::Severity), ParseError> {
+        self.expect(b'<', "Expected \"<\"")?;
+        let start = self.pos;
+        let priority = match self.digits(3) {
+            Some(priority) if priority <= 191 => priority as libc::c_int,
+            _ => {
+                self.pos = start;
+                return self.error("Expected PRI from 0 to 191");
+            },
+        };
+        self.expect(b'>', "Expected \">\"")?;
+
+        let facility = match syslog::Facility::from_code((priority >> 3) as u64) {
+            Some(facility) => facility,
+            None => {
+                self.pos = start;
+                return self.error("Unknown facility");
+            },
+        };
+        Ok((facility, syslog::Severity::from_libc(priority)))
+    }
+
+    /// Message is the rest of data, without trailing garbage usually added by senders.
+    fn message(&mut self) -> Vec<u8> {
+        let rest = self.rest();
+        let len = rest.iter()
+            .rposition(|&c| c != b'\n' && c != b'\r' && c != b'\0')
+            .map_or(0, |pos| pos + 1);
+        self.pos = self.data.len();
+        rest[..len].to_vec()
+    }
+
+    fn structured_data(&mut self) -> Result<Vec<StructuredElement>, ParseError> {
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+            return Ok(Vec::new());
+        }
+        if self.peek() != Some(b'[') {
+            return self.error("Expected structured data");
+        }
+
+        let mut elements = Vec::new();
+        while self.peek() == Some(b'[') {
+            self.pos += 1;
+            let id = self.sd_name()?;
+            let mut params = Vec::new();
+            while self.peek() == Some(b' ') {
+                self.pos += 1;
+                let name = self.sd_name()?;
+                self.expect(b'=', "Expected \"=\"")?;
+                params.push((name, self.sd_value()?));
+            }
+            self.expect(b']', "Expected \"]\"")?;
+            elements.push(StructuredElement { id, params });
+        }
+        Ok(elements)
+    }
+
+    fn sd_name(&mut self) -> Result<String, ParseError> {
+        let len = self.rest()
+            .iter()
+            .position(|&c| c <= b' ' || c >= 0x7f || c == b'=' || c == b']' || c == b'"')
+            .unwrap_or_else(|| self.rest().len());
+        if len == 0 {
+            return self.error("Expected SD-NAME");
+        }
+
+        let name = String::from_utf8_lossy(&self.rest()[..len]).into_owned();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn sd_value(&mut self) -> Result<String, ParseError> {
+        self.expect(b'"', "Expected opening quote")?;
+
+        let mut value = Vec::new();
+        loop {
+            match self.peek() {
+                None => return self.error("Unterminated PARAM-VALUE"),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                },
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c) if c == b'"' || c == b'\\' || c == b']' => {
+                            value.push(c);
+                            self.pos += 1;
+                        },
+                        // Backslash is kept as is before other characters.
+                        _ => value.push(b'\\'),
+                    }
+                },
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+
+        match String::from_utf8(value) {
+            Ok(value) => Ok(value),
+            Err(_) => self.error("PARAM-VALUE is not valid UTF-8"),
+        }
+    }
+
+    /// Whether RFC 5424 TIMESTAMP starts at the current position: `YYYY-MM-DDT...` or NIL.
+    fn rfc5424_timestamp_follows(&self) -> bool {
+        let rest = self.rest();
+        if rest.first() == Some(&b'-') {
+            return matches!(rest.get(1), None | Some(&b' '));
+        }
+
+        rest.len() > 10 && rest[10] == b'T' && rest[..10].iter().enumerate().all(|(pos, c)| match pos {
+            4 | 7 => *c == b'-',
+            _ => c.is_ascii_digit(),
+        })
+    }
+
+    fn rfc5424(&mut self, message: &mut SyslogMessage) -> Result<(), ParseError> {
+        message.timestamp = self.nil_or_token("Expected TIMESTAMP")?;
+        self.expect(b' ', "Expected space after TIMESTAMP")?;
+        message.hostname = self.nil_or_token("Expected HOSTNAME")?;
+        self.expect(b' ', "Expected space after HOSTNAME")?;
+        message.app_name = self.nil_or_token("Expected APP-NAME")?;
+        self.expect(b' ', "Expected space after APP-NAME")?;
+        message.proc_id = self.nil_or_token("Expected PROCID")?;
+        self.expect(b' ', "Expected space after PROCID")?;
+        message.msg_id = self.nil_or_token("Expected MSGID")?;
+        self.expect(b' ', "Expected space after MSGID")?;
+        message.structured_data = self.structured_data()?;
+
+        if self.peek().is_some() {
+            self.expect(b' ', "Expected space after STRUCTURED-DATA")?;
+            if self.rest().starts_with(b"\xef\xbb\xbf") {
+                self.pos += 3;
+            }
+            message.message = self.message();
+        }
+        Ok(())
+    }
+
+    /// `Mmm dd hh:mm:ss`, optionally with year after day, or RFC 3339 timestamp used by some senders.
+    fn rfc3164_timestamp(&mut self) -> Option<String> {
+        let start = self.pos;
+        let rest = self.rest();
+
+        if rest.len() >= 4 && MONTHS.iter().any(|month| rest.starts_with(month.as_bytes())) && rest[3] == b' ' {
+            self.pos += 4;
+            if self.peek() == Some(b' ') {
+                // Day padded with space.
+                self.pos += 1;
+            }
+            if self.digits(2).is_some() && self.skip(b" ") {
+                // Optional year.
+                let year_start = self.pos;
+                if !(self.digits(4).is_some() && self.skip(b" ")) {
+                    self.pos = year_start;
+                }
+
+                if self.time() && self.peek().unwrap_or(b' ') == b' ' {
+                    return Some(self.text_from(start));
+                }
+            }
+        } else if rest.len() >= 10 && rest[..4].iter().all(u8::is_ascii_digit) && rest[4] == b'-' {
+            if let Ok(token) = self.token("") {
+                if token.contains('T') {
+                    return Some(String::from(token));
+                }
+            }
+        }
+
+        self.pos = start;
+        None
+    }
+
+    fn time(&mut self) -> bool {
+        self.digits(2).is_some() && self.skip(b":") && self.digits(2).is_some() && self.skip(b":") &&
+            self.digits(2).is_some()
+    }
+
+    fn skip(&mut self, prefix: &[u8]) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn text_from(&self, start: usize) -> String {
+        String::from_utf8_lossy(&self.data[start..self.pos]).into_owned()
+    }
+
+    /// Parse `TAG[PID]:` or `TAG:` at the current position, if present.
+    fn rfc3164_tag(&mut self) -> Option<(String, Option<String>)> {
+        let rest = self.rest();
+        let len = rest.iter().position(|&c| c == b' ').unwrap_or(rest.len());
+        let token = &rest[..len];
+        if token.len() < 2 || !token.ends_with(b":") {
+            return None;
+        }
+        let token = &token[..token.len() - 1];
+
+        let (tag, pid) = match token.iter().position(|&c| c == b'[') {
+            Some(pos) if token.ends_with(b"]") => (&token[..pos], Some(&token[pos + 1..token.len() - 1])),
+            Some(_) => return None,
+            None => (token, None),
+        };
+        if tag.is_empty() || tag.iter().any(|&c| c <= b' ' || c >= 0x7f) {
+            return None;
+        }
+
+        self.pos += len;
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        Some((text(tag), pid.map(text)))
+    }
+
+    fn rfc3164(&mut self, message: &mut SyslogMessage) {
+        message.timestamp = self.rfc3164_timestamp();
+        if message.timestamp.is_some() {
+            let _ = self.skip(b" ");
+        }
+
+        // Hostname is missing in messages sent to local socket, it is recognized only when followed by a tag.
+        let tag = match self.rfc3164_tag() {
+            Some(tag) => Some(tag),
+            None => {
+                let start = self.pos;
+                let hostname = self.token("").ok().map(String::from);
+                if hostname.is_some() && self.skip(b" ") {
+                    match self.rfc3164_tag() {
+                        Some(tag) => {
+                            message.hostname = hostname;
+                            Some(tag)
+                        },
+                        None => {
+                            self.pos = start;
+                            None
+                        },
+                    }
+                } else {
+                    self.pos = start;
+                    None
+                }
+            },
+        };
+
+        if let Some((app_name, proc_id)) = tag {
+            message.app_name = Some(app_name);
+            message.proc_id = proc_id;
+            let _ = self.skip(b" ");
+        }
+        message.message = self.message();
+    }
+}
+
+/// Parse syslog message in RFC 5424 or RFC 3164 format.
+///
+/// Message is recognized as RFC 5424 if PRI is followed by VERSION, space and TIMESTAMP or `-`. RFC 5424 messages are
+/// parsed strictly. RFC 3164 is handled leniently, like syslog daemons do: timestamp may contain year or be in RFC 3339
+/// format, hostname and tag may be missing, missing PRI means `user.notice`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// let message = log4rs_syslog::parse_message(b"<34>Oct 11 22:14:15 mymachine su: 'su root' failed").unwrap();
+/// assert_eq!(message.facility, log4rs_syslog::Facility::Auth);
+/// assert_eq!(message.severity, log4rs_syslog::Severity::Crit);
+/// assert_eq!(message.app_name, Some(String::from("su")));
+///
+/// let err = log4rs_syslog::parse_message(b"<34>1 2003-10-11T22:14:15.003Z").unwrap_err();
+/// assert_eq!(err.offset, 30);
+/// ```
+pub fn parse_message(data: &[u8]) -> Result<SyslogMessage, ParseError> {
+    let mut parser = Parser { data, pos: 0 };
+
+    let (facility, severity) = if parser.peek() == Some(b'<') {
+        parser.priority()?
+    } else {
+        (syslog::Facility::User, syslog::Severity::Notice)
+    };
+
+    let mut message = SyslogMessage {
+        facility,
+        severity,
+        version: None,
+        timestamp: None,
+        hostname: None,
+        app_name: None,
+        proc_id: None,
+        msg_id: None,
+        structured_data: Vec::new(),
+        message: Vec::new(),
+    };
+
+    // Digits after PRI are VERSION only if the rest of RFC 5424 header follows, like in `<13>1 - ...`, and not in
+    // `<13>2 apples`.
+    let start = parser.pos;
+    match parser.digits(3) {
+        Some(version) if parser.skip(b" ") && parser.rfc5424_timestamp_follows() => {
+            if version != 1 {
+                parser.pos = start;
+                return parser.error("Unsupported version");
+            }
+            message.version = Some(version);
+            parser.rfc5424(&mut message)?;
+        },
+
+        _ => {
+            parser.pos = start;
+            parser.rfc3164(&mut message);
+        },
+    }
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use syslog::{Facility, Severity};
+
+    use super::{parse_message, StructuredElement};
+
+    #[test]
+    fn rfc5424() {
+        let message = parse_message(
+            b"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+              [exampleSDID@32473 iut=\"3\" eventSource=\"Appl\\\"ication\"][examplePriority@32473 class=\"high\"] \
+              \xef\xbb\xbfAn application event\n",
+        ).unwrap();
+
+        assert_eq!(message.facility, Facility::Local4);
+        assert_eq!(message.severity, Severity::Notice);
+        assert_eq!(message.version, Some(1));
+        assert_eq!(message.timestamp, Some(String::from("2003-10-11T22:14:15.003Z")));
+        assert_eq!(message.hostname, Some(String::from("mymachine.example.com")));
+        assert_eq!(message.app_name, Some(String::from("evntslog")));
+        assert_eq!(message.proc_id, None);
+        assert_eq!(message.msg_id, Some(String::from("ID47")));
+        assert_eq!(
+            message.structured_data,
+            vec![
+                StructuredElement {
+                    id: String::from("exampleSDID@32473"),
+                    params: vec![
+                        (String::from("iut"), String::from("3")),
+                        (String::from("eventSource"), String::from("Appl\"ication")),
+                    ],
+                },
+                StructuredElement {
+                    id: String::from("examplePriority@32473"),
+                    params: vec![(String::from("class"), String::from("high"))],
+                },
+            ]
+        );
+        assert_eq!(message.text(), "An application event");
+
+        let message = parse_message(b"<13>1 - - - - - -").unwrap();
+        assert_eq!(message.timestamp, None);
+        assert!(message.message.is_empty());
+    }
+
+    #[test]
+    fn rfc5424_errors() {
+        let offset = |data: &[u8]| parse_message(data).unwrap_err().offset;
+
+        assert_eq!(offset(b"<192>1 - - - - - -"), 1);
+        assert_eq!(offset(b"<13"), 3);
+        assert_eq!(offset(b"<13>2 - - - - - -"), 4);
+        assert_eq!(offset(b"<13>1 - - - - -"), 15);
+        assert_eq!(offset(b"<13>1 - - - - - x"), 16);
+        assert_eq!(offset(b"<13>1 - - - - - [id name=\"value]"), 32);
+        assert_eq!(offset(b"<13>1 - ho\x01st - - - -"), 10);
+    }
+
+    #[test]
+    fn rfc3164() {
+        let message = parse_message(b"<34>Oct 11 22:14:15 mymachine su: 'su root' failed").unwrap();
+        assert_eq!(message.facility, Facility::Auth);
+        assert_eq!(message.severity, Severity::Crit);
+        assert_eq!(message.version, None);
+        assert_eq!(message.timestamp, Some(String::from("Oct 11 22:14:15")));
+        assert_eq!(message.hostname, Some(String::from("mymachine")));
+        assert_eq!(message.app_name, Some(String::from("su")));
+        assert_eq!(message.text(), "'su root' failed");
+
+        // Not RFC 5424 version.
+        let message = parse_message(b"<13>2 apples").unwrap();
+        assert_eq!(message.version, None);
+        assert_eq!(message.timestamp, None);
+        assert_eq!(message.text(), "2 apples");
+        assert_eq!(parse_message(b"<13>1 2003 report").unwrap().text(), "1 2003 report");
+
+        // As sent by glibc to local socket.
+        let message = parse_message(b"<30>Oct  1 02:03:04 daemon[123]: started\0").unwrap();
+        assert_eq!(message.timestamp, Some(String::from("Oct  1 02:03:04")));
+        assert_eq!(message.hostname, None);
+        assert_eq!(message.app_name, Some(String::from("daemon")));
+        assert_eq!(message.proc_id, Some(String::from("123")));
+        assert_eq!(message.text(), "started");
+
+        let message = parse_message(b"<30>Oct 11 2003 22:14:15 host app: with year").unwrap();
+        assert_eq!(message.timestamp, Some(String::from("Oct 11 2003 22:14:15")));
+        assert_eq!(message.hostname, Some(String::from("host")));
+
+        let message = parse_message(b"<30>2003-10-11T22:14:15+02:00 host app[1]: rfc3339").unwrap();
+        assert_eq!(message.timestamp, Some(String::from("2003-10-11T22:14:15+02:00")));
+        assert_eq!(message.proc_id, Some(String::from("1")));
+
+        let message = parse_message(b"no header at all: message").unwrap();
+        assert_eq!(message.facility, Facility::User);
+        assert_eq!(message.severity, Severity::Notice);
+        assert_eq!(message.timestamp, None);
+        assert_eq!(message.app_name, None);
+        assert_eq!(message.text(), "no header at all: message");
+    }
+}
@@ -0,0 +1,413 @@
+#![forbid(unsafe_code)]
+
+use std;
+use std::io::Read;
+
+use log;
+use parse;
+use syslog;
+
+// How often threads of receiver check whether it was stopped.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+const MAX_DATAGRAM_LEN: usize = 65536;
+
+// TCP connections which send longer frames are closed.
+const MAX_FRAME_LEN: usize = 65536;
+
+// TCP connections which send nothing for this long are closed.
+const IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+const DEFAULT_MAX_CONNECTIONS: usize = 64;
+
+/// Map syslog severity back to `log` level: `LOG_EMERG`..`LOG_ERR` to `Error`, `LOG_WARNING` to `Warn`,
+/// `LOG_NOTICE` and `LOG_INFO` to `Info`, `LOG_DEBUG` to `Debug`.
+pub(crate) fn severity_level(severity: syslog::Severity) -> log::Level {
+    match severity {
+        syslog::Severity::Emerg | syslog::Severity::Alert | syslog::Severity::Crit | syslog::Severity::Err => {
+            log::Level::Error
+        },
+        syslog::Severity::Warning => log::Level::Warn,
+        syslog::Severity::Notice | syslog::Severity::Info => log::Level::Info,
+        syslog::Severity::Debug => log::Level::Debug,
+    }
+}
+
+struct Dispatcher {
+    target_prefix: String,
+}
+
+impl Dispatcher {
+    fn dispatch(&self, data: &[u8]) {
+        match parse::parse_message(data) {
+            Ok(message) => {
+                let target = message.app_name.as_ref().map_or("syslog", |app_name| &app_name[..]);
+                self.log(severity_level(message.severity), target, &message.text());
+            },
+
+            // Do not lose anything.
+            Err(_) => self.log(log::Level::Info, "syslog", &String::from_utf8_lossy(data)),
+        }
+    }
+
+    fn log(&self, level: log::Level, target: &str, text: &str) {
+        let target = format!("{}{}", self.target_prefix, target);
+        let metadata = log::Metadata::builder().level(level).target(&target).build();
+
+        let logger = log::logger();
+        if level <= log::max_level() && logger.enabled(&metadata) {
+            logger.log(&log::Record::builder()
+                .metadata(metadata)
+                .args(format_args!("{}", text))
+                .build());
+        }
+    }
+}
+
+enum Listener {
+    Unix(std::os::unix::net::UnixDatagram, std::path::PathBuf),
+    Udp(std::net::UdpSocket),
+    Tcp(std::net::TcpListener),
+}
+
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut | std::io::ErrorKind::Interrupted
+    )
+}
+
+fn frame_too_long() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, "TCP frame is too long")
+}
+
+/// Take the next complete TCP frame (RFC 6587), octet-counted or terminated by line feed. Fails if frame is longer
+/// than `MAX_FRAME_LEN`.
+fn take_frame(pending: &mut Vec<u8>) -> std::io::Result<Option<Vec<u8>>> {
+    let octet_counting = match pending.first() {
+        Some(c) => c.is_ascii_digit(),
+        None => return Ok(None),
+    };
+
+    if octet_counting {
+        if let Some(space) = pending.iter().position(|&c| c == b' ') {
+            let len = std::str::from_utf8(&pending[..space])
+                .ok()
+                .and_then(|len| len.parse::<usize>().ok());
+            if let Some(len) = len {
+                let end = match (space + 1).checked_add(len) {
+                    Some(end) if len <= MAX_FRAME_LEN => end,
+                    _ => return Err(frame_too_long()),
+                };
+                if pending.len() < end {
+                    return Ok(None);
+                }
+                let frame = pending[space + 1..end].to_vec();
+                let _ = pending.drain(..end);
+                return Ok(Some(frame));
+            }
+        }
+        // Not a length, fall back to line feed.
+    }
+
+    match pending.iter().position(|&c| c == b'\n') {
+        Some(end) => {
+            let frame = pending[..end].to_vec();
+            let _ = pending.drain(..end + 1);
+            Ok(Some(frame))
+        },
+        None if pending.len() > MAX_FRAME_LEN => Err(frame_too_long()),
+        None => Ok(None),
+    }
+}
+
+fn serve_datagrams<F>(recv: F, dispatcher: &Dispatcher, stop: &std::sync::atomic::AtomicBool)
+where
+    F: Fn(&mut [u8]) -> std::io::Result<usize>,
+{
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+    while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+        match recv(&mut buf) {
+            Ok(len) => dispatcher.dispatch(&buf[..len]),
+            Err(ref err) if is_timeout(err) => (),
+            Err(_) => return,
+        }
+    }
+}
+
+fn serve_stream(
+    mut stream: std::net::TcpStream,
+    dispatcher: &Dispatcher,
+    stop: &std::sync::atomic::AtomicBool,
+) -> std::io::Result<()> {
+    // Accepted sockets inherit O_NONBLOCK from listener on BSD and macOS.
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let mut pending = Vec::new();
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+    let mut last_read = std::time::Instant::now();
+    while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => {
+                last_read = std::time::Instant::now();
+                pending.extend_from_slice(&buf[..len]);
+                while let Some(frame) = take_frame(&mut pending)? {
+                    dispatcher.dispatch(&frame);
+                }
+            },
+            Err(ref err) if is_timeout(err) => {
+                if last_read.elapsed() >= IDLE_TIMEOUT {
+                    break;
+                }
+            },
+            Err(err) => return Err(err),
+        }
+    }
+
+    // Unterminated last frame.
+    if !pending.is_empty() {
+        dispatcher.dispatch(&pending);
+    }
+    Ok(())
+}
+
+fn join_finished(connections: &mut Vec<std::thread::JoinHandle<()>>) {
+    let (finished, running): (Vec<_>, Vec<_>) = connections
+        .drain(..)
+        .partition(std::thread::JoinHandle::is_finished);
+    *connections = running;
+    for connection in finished {
+        let _ = connection.join();
+    }
+}
+
+fn serve(
+    listener: Listener,
+    max_connections: usize,
+    dispatcher: std::sync::Arc<Dispatcher>,
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+) {
+    match listener {
+        Listener::Unix(socket, path) => {
+            serve_datagrams(|buf| socket.recv(buf), &dispatcher, &stop);
+            let _ = std::fs::remove_file(path);
+        },
+
+        Listener::Udp(socket) => serve_datagrams(|buf| socket.recv(buf), &dispatcher, &stop),
+
+        Listener::Tcp(listener) => {
+            let mut connections = Vec::new();
+            while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        join_finished(&mut connections);
+                        if connections.len() >= max_connections {
+                            // Close connection right away.
+                            continue;
+                        }
+
+                        let dispatcher = dispatcher.clone();
+                        let stop = stop.clone();
+                        let connection = std::thread::Builder::new()
+                            .name(String::from("syslog-receiver-tcp"))
+                            .spawn(move || {
+                                let _ = serve_stream(stream, &dispatcher, &stop);
+                            });
+                        if let Ok(connection) = connection {
+                            connections.push(connection);
+                        }
+                    },
+                    Err(ref err) if is_timeout(err) => std::thread::sleep(POLL_INTERVAL),
+                    Err(_) => break,
+                }
+            }
+
+            for connection in connections {
+                let _ = connection.join();
+            }
+        },
+    }
+}
+
+/// Receiver of syslog messages which re-emits them through the `log` crate, so that they are processed by the
+/// active log4rs configuration like any other records.
+///
+/// Messages are parsed by `parse_message()`. Target of records is APP-NAME (or `syslog` if missing) with optional
+/// prefix, level is mapped from severity: `LOG_EMERG`..`LOG_ERR` become `Error`, `LOG_WARNING` becomes `Warn`,
+/// `LOG_NOTICE` and `LOG_INFO` become `Info`, `LOG_DEBUG` becomes `Debug`. Data which can not be parsed is emitted
+/// as is with target `syslog` and level `Info`.
+///
+/// Make sure that records emitted by receiver do not come back to it through a syslog appender.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// let handle = log4rs_syslog::SyslogReceiver::udp("127.0.0.1:0".parse().unwrap())
+///     .unwrap()
+///     .target_prefix("legacy::")
+///     .spawn()
+///     .unwrap();
+///
+/// // Records from messages sent to `handle.local_addr()` are logged with targets like "legacy::helper".
+///
+/// handle.stop();
+/// ```
+pub struct SyslogReceiver {
+    listener: Listener,
+    target_prefix: String,
+    max_connections: usize,
+}
+
+impl std::fmt::Debug for SyslogReceiver {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "SyslogReceiver {{target_prefix: {:?}, max_connections: {}}}",
+            self.target_prefix, self.max_connections
+        )
+    }
+}
+
+impl SyslogReceiver {
+    fn new(listener: Listener) -> Self {
+        Self {
+            listener,
+            target_prefix: String::new(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+        }
+    }
+
+    /// Bind Unix datagram socket. Socket file should not exist, it is removed when receiver is stopped.
+    pub fn unix<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let socket = std::os::unix::net::UnixDatagram::bind(&path)?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Self::new(Listener::Unix(socket, path.as_ref().to_path_buf())))
+    }
+
+    /// Bind UDP socket. Use port 0 to get any free port, see `SyslogReceiverHandle::local_addr()`.
+    pub fn udp(addr: std::net::SocketAddr) -> std::io::Result<Self> {
+        let socket = std::net::UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Self::new(Listener::Udp(socket)))
+    }
+
+    /// Listen on TCP socket. Both octet-counting and non-transparent framing (RFC 6587) are accepted. Connections
+    /// which send frames longer than 64 KiB or stay idle for a minute are closed.
+    pub fn tcp(addr: std::net::SocketAddr) -> std::io::Result<Self> {
+        let listener = std::net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self::new(Listener::Tcp(listener)))
+    }
+
+    /// Prepend prefix to targets of emitted records.
+    pub fn target_prefix(mut self, prefix: &str) -> Self {
+        self.target_prefix = String::from(prefix);
+        self
+    }
+
+    /// Maximum number of simultaneous TCP connections, every one is served by its own thread. Connections over the
+    /// limit are closed right after accepting. Default is 64.
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Start receiving messages on a background thread.
+    pub fn spawn(self) -> std::io::Result<SyslogReceiverHandle> {
+        let local_addr = match self.listener {
+            Listener::Unix(..) => None,
+            Listener::Udp(ref socket) => Some(socket.local_addr()?),
+            Listener::Tcp(ref listener) => Some(listener.local_addr()?),
+        };
+
+        let dispatcher = std::sync::Arc::new(Dispatcher {
+            target_prefix: self.target_prefix,
+        });
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let listener = self.listener;
+        let max_connections = self.max_connections;
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name(String::from("syslog-receiver"))
+            .spawn(move || serve(listener, max_connections, dispatcher, thread_stop))?;
+
+        Ok(SyslogReceiverHandle {
+            local_addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Handle of running `SyslogReceiver`. Receiver is stopped when handle is dropped.
+#[derive(Debug)]
+pub struct SyslogReceiverHandle {
+    local_addr: Option<std::net::SocketAddr>,
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl SyslogReceiverHandle {
+    /// Address of UDP or TCP socket, `None` for Unix socket.
+    pub fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        self.local_addr
+    }
+
+    /// Stop receiver and wait until messages which are already received are emitted.
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for SyslogReceiverHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{take_frame, MAX_FRAME_LEN};
+
+    fn frames(data: &[u8]) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let mut pending = data.to_vec();
+        let mut frames = Vec::new();
+        while let Some(frame) = take_frame(&mut pending).unwrap() {
+            frames.push(frame);
+        }
+        (frames, pending)
+    }
+
+    #[test]
+    fn framing() {
+        assert_eq!(
+            frames(b"5 a\nbcd<13>x\n3 yz"),
+            (vec![b"a\nbcd".to_vec(), b"<13>x".to_vec()], b"3 yz".to_vec())
+        );
+        assert_eq!(frames(b"2x y\n"), (vec![b"2x y".to_vec()], Vec::new()));
+        assert_eq!(frames(b"<13>no line feed"), (Vec::new(), b"<13>no line feed".to_vec()));
+    }
+
+    #[test]
+    fn too_long() {
+        let mut pending = b"18446744073709551615 x".to_vec();
+        assert!(take_frame(&mut pending).is_err());
+
+        let mut pending = format!("{} x", MAX_FRAME_LEN + 1).into_bytes();
+        assert!(take_frame(&mut pending).is_err());
+
+        let mut pending = vec![b'x'; MAX_FRAME_LEN + 1];
+        assert!(take_frame(&mut pending).is_err());
+
+        let mut pending = vec![b'x'; MAX_FRAME_LEN];
+        assert_eq!(take_frame(&mut pending).unwrap(), None);
+    }
+}
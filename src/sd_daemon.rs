@@ -0,0 +1,108 @@
+#![forbid(unsafe_code)]
+
+use std;
+
+use libc;
+use log;
+use log4rs;
+use syslog;
+
+/// Encoder which prepends sd-daemon(3) priority prefix, like `<3>` for `LOG_ERR`, to the output of another encoder.
+///
+/// Under systemd, journald reads such prefixes from stdout and stderr of services, so console appenders may carry
+/// correct priorities without syslog socket. Level is mapped in the same way as by `SyslogAppender`. Prefix applies
+/// to the first line of the output only.
+///
+/// Registered by `register()` as `sd-daemon`:
+///
+/// ```yaml
+/// encoder:
+///   kind: sd-daemon
+///   encoder:
+///     kind: pattern
+///     pattern: "{m}{n}"
+///   level_map:
+///     Error: LOG_CRIT
+///   facility: Daemon
+/// ```
+///
+/// All fields are optional, default inner encoder is `pattern` with default pattern.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs;
+/// extern crate log4rs_syslog;
+///
+/// let encoder = log4rs_syslog::SdDaemonEncoder::new(Box::new(
+///     log4rs::encode::pattern::PatternEncoder::new("{m}{n}"),
+/// )).facility(log4rs_syslog::Facility::Daemon);
+///
+/// // Pass encoder to console appender writing to stderr.
+/// ```
+pub struct SdDaemonEncoder {
+    encoder: Box<log4rs::encode::Encode>,
+    level_map: Option<Box<syslog::LevelMap>>,
+    facility: Option<syslog::Facility>,
+}
+
+impl std::fmt::Debug for SdDaemonEncoder {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "SdDaemonEncoder {{encoder: {:?}, level_map: {}, facility: {:?}}}",
+            self.encoder,
+            match self.level_map {
+                Some(_) => "Some(_)",
+                None => "None",
+            },
+            self.facility
+        )
+    }
+}
+
+impl SdDaemonEncoder {
+    /// Wrap encoder. Default level mapping is used and facility is not included.
+    pub fn new(encoder: Box<log4rs::encode::Encode>) -> Self {
+        Self {
+            encoder,
+            level_map: None,
+            facility: None,
+        }
+    }
+
+    /// Set custom log level mapping. It should return only `libc::LOG_EMERG`..`libc::LOG_DEBUG`.
+    pub fn level_map(mut self, level_map: Box<syslog::LevelMap>) -> Self {
+        self.level_map = Some(level_map);
+        self
+    }
+
+    /// Include facility into prefix, like `<27>` for `LOG_DAEMON | LOG_ERR`.
+    pub fn facility(mut self, facility: syslog::Facility) -> Self {
+        self.facility = Some(facility);
+        self
+    }
+
+    fn priority(&self, level: log::Level) -> libc::c_int {
+        let level = match self.level_map {
+            Some(ref level_map) => level_map(level),
+            None => syslog::default_level_map(level),
+        };
+
+        match self.facility {
+            Some(facility) => Into::<libc::c_int>::into(facility) | level,
+            None => level,
+        }
+    }
+}
+
+impl log4rs::encode::Encode for SdDaemonEncoder {
+    fn encode(
+        &self,
+        w: &mut log4rs::encode::Write,
+        record: &log::Record,
+    ) -> std::result::Result<(), Box<std::error::Error + Sync + Send>> {
+        write!(w, "<{}>", self.priority(record.level()))?;
+        self.encoder.encode(w, record)
+    }
+}
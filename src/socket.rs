@@ -0,0 +1,462 @@
+use std;
+use std::io::Write;
+
+use backend;
+use error;
+use fork;
+use ident;
+use libc;
+use syslog;
+
+/// Usual locations of local syslog socket: Linux, macOS, FreeBSD.
+pub(crate) const DEFAULT_SOCKET_PATHS: &[&str] = &["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+/// Format of records sent by `SocketBackend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// BSD syslog protocol (RFC 3164): `<PRI>Mmm dd hh:mm:ss HOSTNAME IDENT[PID]: MSG`, with local time. Hostname is
+    /// omitted for Unix sockets, like libc does.
+    Rfc3164,
+    /// Syslog protocol (RFC 5424): `<PRI>1 TIMESTAMP HOSTNAME IDENT PID - - MSG`, with local time and UTC offset.
+    Rfc5424,
+}
+
+/// Framing of records sent over TCP (RFC 6587).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Every record is prefixed by its length in bytes and a space. Messages may contain line breaks.
+    OctetCounting,
+    /// Every record is terminated by a line feed. Line breaks in messages split them into several records.
+    NonTransparent,
+}
+
+/// Address of local Unix socket: path or Linux abstract-namespace name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum UnixAddr {
+    Path(std::path::PathBuf),
+    Abstract(String),
+}
+
+impl UnixAddr {
+    /// Names starting with `@` are abstract, like in `ss` and systemd.
+    fn parse(addr: &str) -> Self {
+        if let Some(name) = addr.strip_prefix('@') {
+            UnixAddr::Abstract(String::from(name))
+        } else {
+            UnixAddr::Path(std::path::PathBuf::from(addr))
+        }
+    }
+
+    fn connect(&self) -> std::io::Result<std::os::unix::net::UnixDatagram> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        match *self {
+            UnixAddr::Path(ref path) => socket.connect(path)?,
+            UnixAddr::Abstract(ref name) => connect_abstract(&socket, name)?,
+        }
+        Ok(socket)
+    }
+}
+
+impl std::fmt::Display for UnixAddr {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            UnixAddr::Path(ref path) => write!(formatter, "{}", path.display()),
+            UnixAddr::Abstract(ref name) => write!(formatter, "@{}", name),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn connect_abstract(socket: &std::os::unix::net::UnixDatagram, name: &str) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+
+    socket.connect_addr(&std::os::unix::net::SocketAddr::from_abstract_name(name)?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn connect_abstract(_socket: &std::os::unix::net::UnixDatagram, _name: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "Abstract socket names are supported only on Linux",
+    ))
+}
+
+/// Check whether local Unix socket with given address (path or `@name`) accepts connections.
+pub(crate) fn unix_socket_available(addr: &str) -> bool {
+    UnixAddr::parse(addr).connect().is_ok()
+}
+
+/// Convert error of sending a record through datagram socket. `EMSGSIZE` becomes `SyslogError::MessageTooLarge` with
+/// the size of socket's send buffer as limit.
+pub(crate) fn datagram_send_error<S>(socket: &S, size: usize, err: std::io::Error) -> error::SyslogError
+where
+    S: std::os::unix::io::AsRawFd,
+{
+    if err.raw_os_error() != Some(libc::EMSGSIZE) {
+        return err.into();
+    }
+
+    let mut limit: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_SNDBUF,
+            &mut limit as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if res != 0 {
+        return err.into();
+    }
+
+    error::SyslogError::MessageTooLarge {
+        size,
+        limit: limit as usize,
+    }
+}
+
+#[derive(Debug)]
+enum Transport {
+    Unix(Vec<UnixAddr>),
+    Udp(std::net::SocketAddr),
+    Tcp(std::net::SocketAddr, Framing),
+}
+
+enum Connection {
+    Unix(std::os::unix::net::UnixDatagram),
+    Udp(std::net::UdpSocket),
+    Tcp(std::net::TcpStream),
+}
+
+impl Connection {
+    // Returns connection and description of address it is connected to.
+    fn open(transport: &Transport) -> std::io::Result<(Self, String)> {
+        Ok(match *transport {
+            Transport::Unix(ref addrs) => {
+                // Error of the last candidate is reported.
+                let mut last_err = std::io::Error::new(std::io::ErrorKind::NotFound, "No socket paths");
+                for addr in addrs {
+                    match addr.connect() {
+                        Ok(socket) => return Ok((Connection::Unix(socket), addr.to_string())),
+                        Err(err) => last_err = err,
+                    }
+                }
+                return Err(last_err);
+            },
+
+            Transport::Udp(addr) => {
+                let local_addr: std::net::SocketAddr = if addr.is_ipv4() {
+                    (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+                };
+                let socket = std::net::UdpSocket::bind(local_addr)?;
+                socket.connect(addr)?;
+                (Connection::Udp(socket), addr.to_string())
+            },
+
+            Transport::Tcp(addr, _) => (Connection::Tcp(std::net::TcpStream::connect(addr)?), addr.to_string()),
+        })
+    }
+
+    fn send(&mut self, record: &[u8]) -> Result<(), error::SyslogError> {
+        match *self {
+            Connection::Unix(ref socket) => socket
+                .send(record)
+                .map(|_| ())
+                .map_err(|err| datagram_send_error(socket, record.len(), err)),
+            Connection::Udp(ref socket) => socket
+                .send(record)
+                .map(|_| ())
+                .map_err(|err| datagram_send_error(socket, record.len(), err)),
+            Connection::Tcp(ref mut stream) => Ok(stream.write_all(record)?),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match *self {
+            Connection::Unix(_) | Connection::Udp(_) => Ok(()),
+            Connection::Tcp(ref mut stream) => stream.flush(),
+        }
+    }
+}
+
+struct State {
+    ident: String,
+    hostname: String,
+    connection: Option<Connection>,
+    destination: Option<String>,
+    record: Vec<u8>,
+}
+
+/// Backend which sends records directly to a syslog socket, without using `libc`'s syslog().
+///
+/// Connection is opened when appender is activated and re-opened once if sending fails. Local Unix sockets are
+/// configured with `SyslogAppenderBuilder::socket_paths()`, this type is needed only for remote daemons.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// let backend = log4rs_syslog::SocketBackend::tcp(
+///     "127.0.0.1:514".parse().unwrap(),
+///     log4rs_syslog::Framing::OctetCounting,
+/// );
+/// let appender = log4rs_syslog::SyslogAppender::builder()
+///     .openlog("my-daemon", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+///     .backend(Box::new(backend))
+///     .build();
+/// ```
+pub struct SocketBackend {
+    transport: Transport,
+    format: Format,
+    state: std::sync::Mutex<State>,
+}
+
+impl std::fmt::Debug for SocketBackend {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "SocketBackend {{transport: {:?}, format: {:?}}}",
+            self.transport, self.format
+        )
+    }
+}
+
+impl SocketBackend {
+    fn new(transport: Transport, format: Format) -> Self {
+        Self {
+            transport,
+            format,
+            state: std::sync::Mutex::new(State {
+                ident: String::new(),
+                hostname: String::new(),
+                connection: None,
+                destination: None,
+                record: Vec::new(),
+            }),
+        }
+    }
+
+    /// Send records to the first local Unix datagram socket from list which accepts connection. Candidates are
+    /// tried in order on every (re)connection, names starting with `@` are Linux abstract-namespace names. Socket
+    /// which was picked is reported by `SyslogBackend::destination()`. Default format is `Format::Rfc3164`.
+    pub(crate) fn unix_candidates<S: AsRef<str>>(addrs: &[S]) -> Self {
+        Self::new(
+            Transport::Unix(addrs.iter().map(|addr| UnixAddr::parse(addr.as_ref())).collect()),
+            Format::Rfc3164,
+        )
+    }
+
+    /// Send records over UDP (RFC 5426). Default format is `Format::Rfc5424`.
+    pub fn udp(addr: std::net::SocketAddr) -> Self {
+        Self::new(Transport::Udp(addr), Format::Rfc5424)
+    }
+
+    /// Send records over TCP (RFC 6587). Default format is `Format::Rfc5424`.
+    pub fn tcp(addr: std::net::SocketAddr, framing: Framing) -> Self {
+        Self::new(Transport::Tcp(addr, framing), Format::Rfc5424)
+    }
+
+    /// Set format of records.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn format_record(&self, state: &mut State, priority: libc::c_int, message: &[u8]) {
+        let time = LocalTime::now();
+        let pid = fork::pid();
+
+        let record = &mut state.record;
+        record.clear();
+
+        // Writing into `Vec` never fails.
+        match self.format {
+            Format::Rfc3164 => {
+                let _ = write!(record, "<{}>{} ", priority, time.rfc3164());
+                match self.transport {
+                    // Local daemon adds hostname itself.
+                    Transport::Unix(_) => (),
+                    Transport::Udp(_) | Transport::Tcp(..) => {
+                        let _ = write!(record, "{} ", state.hostname);
+                    },
+                }
+                let _ = write!(record, "{}[{}]: ", state.ident, pid);
+            },
+
+            Format::Rfc5424 => {
+                let hostname = if state.hostname.is_empty() {
+                    "-"
+                } else {
+                    &state.hostname[..]
+                };
+                let _ = write!(
+                    record,
+                    "<{}>1 {} {} {} {} - - ",
+                    priority,
+                    time.rfc3339(),
+                    hostname,
+                    state.ident,
+                    pid
+                );
+            },
+        }
+
+        match self.transport {
+            Transport::Tcp(_, Framing::OctetCounting) => {
+                record.extend_from_slice(message);
+                let prefix = format!("{} ", record.len());
+                let _ = record.splice(..0, prefix.into_bytes());
+            },
+
+            Transport::Tcp(_, Framing::NonTransparent) => {
+                // Line break added by encoder would produce an empty record.
+                record.extend_from_slice(syslog::trim_line_breaks(message));
+                record.push(b'\n');
+            },
+
+            Transport::Unix(_) | Transport::Udp(_) => record.extend_from_slice(message),
+        }
+    }
+}
+
+impl backend::SyslogBackend for SocketBackend {
+    fn open(&self, ident: Option<&str>) -> Result<(), error::SyslogError> {
+        let mut state = self.lock();
+        state.ident = match ident {
+            Some(ident) => String::from(ident),
+            None => ident::auto_ident(None),
+        };
+        state.hostname = ident::hostname().unwrap_or_default();
+        let (connection, destination) = Connection::open(&self.transport)?;
+        state.connection = Some(connection);
+        state.destination = Some(destination);
+        Ok(())
+    }
+
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), error::SyslogError> {
+        // Facility is not configured (`LOG_KERN` is rejected by `SyslogAppenderBuilder::try_build()`), use the same
+        // default as libc.
+        let priority = if priority & libc::LOG_FACMASK == 0 {
+            priority | libc::LOG_USER
+        } else {
+            priority
+        };
+
+        let mut state = self.lock();
+        self.format_record(&mut state, priority, message);
+
+        let state = &mut *state;
+        if let Some(ref mut connection) = state.connection {
+            match connection.send(&state.record) {
+                Ok(()) => return Ok(()),
+                // Reconnecting does not help.
+                Err(err @ error::SyslogError::MessageTooLarge { .. }) => return Err(err),
+                Err(_) => (),
+            }
+        }
+
+        // Daemon may be restarted, try to reconnect once.
+        state.connection = None;
+        let (mut connection, destination) = Connection::open(&self.transport)?;
+        connection.send(&state.record)?;
+        state.connection = Some(connection);
+        state.destination = Some(destination);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), error::SyslogError> {
+        if let Some(ref mut connection) = self.lock().connection {
+            connection.flush()?;
+        }
+        Ok(())
+    }
+
+    fn close(&self) {
+        self.lock().connection = None;
+    }
+
+    fn after_fork(&self) {
+        // Closing inherited descriptor does not affect the parent. If connection fails, `send()` retries.
+        let mut state = self.lock();
+        state.connection = None;
+        if let Ok((connection, destination)) = Connection::open(&self.transport) {
+            state.connection = Some(connection);
+            state.destination = Some(destination);
+        }
+    }
+
+    fn destination(&self) -> Option<String> {
+        self.lock().destination.clone()
+    }
+}
+
+struct LocalTime {
+    tm: libc::tm,
+    micros: u32,
+}
+
+impl LocalTime {
+    fn now() -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let secs = now.as_secs() as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        unsafe {
+            // On failure `tm` stays zeroed, which is still formatted correctly.
+            let _ = libc::localtime_r(&secs, &mut tm);
+        }
+
+        Self {
+            tm,
+            micros: now.subsec_micros(),
+        }
+    }
+
+    /// `Mmm dd hh:mm:ss`, day is padded with space.
+    fn rfc3164(&self) -> String {
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+
+        format!(
+            "{} {:2} {:02}:{:02}:{:02}",
+            MONTHS[self.tm.tm_mon as usize % 12],
+            self.tm.tm_mday,
+            self.tm.tm_hour,
+            self.tm.tm_min,
+            self.tm.tm_sec
+        )
+    }
+
+    /// `YYYY-MM-DDThh:mm:ss.uuuuuu+hh:mm`.
+    fn rfc3339(&self) -> String {
+        let offset = self.tm.tm_gmtoff / 60;
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}{}{:02}:{:02}",
+            self.tm.tm_year + 1900,
+            self.tm.tm_mon + 1,
+            self.tm.tm_mday,
+            self.tm.tm_hour,
+            self.tm.tm_min,
+            self.tm.tm_sec,
+            self.micros,
+            if offset < 0 { '-' } else { '+' },
+            offset.abs() / 60,
+            offset.abs() % 60
+        )
+    }
+}
+
@@ -1,10 +1,19 @@
 use std;
 
+use backend;
+use emergency;
+use error;
+#[cfg(feature = "file")]
+use expand;
+#[cfg(feature = "file")]
+use file;
+use ident;
 use libc;
 use log;
 use log4rs;
 #[cfg(feature = "file")]
 use serde;
+use socket;
 
 const DEFAULT_BUF_SIZE: usize = 4096;
 
@@ -17,23 +26,113 @@ thread_local! {
 
 struct BufWriter {}
 
+/// Forget about buffer contents left by the interrupted `append()`, if any.
+pub(crate) fn reset_persistent_buf() {
+    PERSISTENT_BUF.with(|pers_buf| {
+        if let Ok(mut pers_buf) = pers_buf.try_borrow_mut() {
+            pers_buf.set_position(0);
+        }
+    });
+}
+
 impl BufWriter {
     fn new() -> Self {
         PERSISTENT_BUF.with(|pers_buf| pers_buf.borrow_mut().set_position(0));
         Self {}
     }
 
-    fn as_c_str(&mut self) -> *const libc::c_char {
+    fn with_message<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        PERSISTENT_BUF.with(|pers_buf| {
+            let pers_buf = pers_buf.borrow();
+            f(&pers_buf.get_ref()[..pers_buf.position() as usize])
+        })
+    }
+
+    fn sanitize(&mut self, sanitize: Sanitize) {
         use std::io::Write;
 
+        if sanitize == Sanitize::Off {
+            return;
+        }
+
         PERSISTENT_BUF.with(|pers_buf| {
             let mut pers_buf = pers_buf.borrow_mut();
-            pers_buf.write_all(&[0; 1]).unwrap();
-            pers_buf.get_ref().as_ptr() as *const libc::c_char
+            let mut sanitized = Vec::new();
+            let len = {
+                let message = &pers_buf.get_ref()[..pers_buf.position() as usize];
+                let message = trim_line_breaks(message);
+                if message.iter().any(|&c| is_control(c)) {
+                    sanitize_message(message, sanitize, &mut sanitized);
+                }
+                message.len()
+            };
+
+            if sanitized.is_empty() {
+                pers_buf.set_position(len as u64);
+            } else {
+                pers_buf.set_position(0);
+                pers_buf.write_all(&sanitized).unwrap();
+            }
         })
     }
 }
 
+/// How to treat control characters (including line breaks) in encoded messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "file", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "file", serde(rename_all = "lowercase"))]
+pub enum Sanitize {
+    /// Send messages as produced by encoder. Message is truncated at the first NUL character.
+    Off,
+    /// Remove trailing line breaks, replace other control characters by escape sequences like `\n` and `\x1b`.
+    Escape,
+    /// Remove trailing line breaks, replace other control characters by spaces.
+    Replace,
+}
+
+// Tab is allowed.
+fn is_control(c: u8) -> bool {
+    (c < b' ' && c != b'\t') || c == 0x7f
+}
+
+pub(crate) fn trim_line_breaks(message: &[u8]) -> &[u8] {
+    let len = message
+        .iter()
+        .rposition(|&c| c != b'\n' && c != b'\r')
+        .map_or(0, |pos| pos + 1);
+    &message[..len]
+}
+
+fn sanitize_message(message: &[u8], sanitize: Sanitize, sanitized: &mut Vec<u8>) {
+    const HEX_DIGITS: &[u8] = b"0123456789abcdef";
+
+    for &c in message {
+        if !is_control(c) {
+            sanitized.push(c);
+            continue;
+        }
+
+        match sanitize {
+            Sanitize::Off => sanitized.push(c),
+            Sanitize::Escape => match c {
+                b'\0' => sanitized.extend_from_slice(b"\\0"),
+                b'\n' => sanitized.extend_from_slice(b"\\n"),
+                b'\r' => sanitized.extend_from_slice(b"\\r"),
+                c => sanitized.extend_from_slice(&[
+                    b'\\',
+                    b'x',
+                    HEX_DIGITS[(c >> 4) as usize],
+                    HEX_DIGITS[(c & 0xf) as usize],
+                ]),
+            },
+            Sanitize::Replace => sanitized.push(b' '),
+        }
+    }
+}
+
 impl std::io::Write for BufWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         PERSISTENT_BUF.with(|pers_buf| pers_buf.borrow_mut().write(buf))
@@ -49,10 +148,142 @@ impl log4rs::encode::Write for BufWriter {}
 /// Function for mapping rust's `log` levels to `libc`'s log levels.
 pub type LevelMap = Fn(log::Level) -> libc::c_int + Send + Sync;
 
+/// Default mapping of rust's `log` levels to `libc`'s log levels.
+pub(crate) fn default_level_map(level: log::Level) -> libc::c_int {
+    match level {
+        log::Level::Error => libc::LOG_ERR,
+        log::Level::Warn => libc::LOG_WARNING,
+        log::Level::Info => libc::LOG_INFO,
+        log::Level::Debug | log::Level::Trace => libc::LOG_DEBUG,
+    }
+}
+
+struct Settings {
+    level_map: Option<std::sync::Arc<LevelMap>>,
+    facility: Option<Facility>,
+    log_mask: Option<LogMask>,
+    sanitize: Sanitize,
+}
+
+impl Settings {
+    fn map_level(&self, level: log::Level) -> Result<libc::c_int, error::SyslogError> {
+        match self.level_map {
+            // Level map is not validated by `SyslogAppenderBuilder::build()`.
+            Some(ref level_map) => {
+                let libc_level = level_map(level);
+                validate_severity(level, libc_level)?;
+                Ok(libc_level)
+            },
+
+            None => Ok(default_level_map(level)),
+        }
+    }
+
+    // Priority of message with given libc level, `None` if level is masked.
+    fn priority(&self, level: libc::c_int) -> Option<libc::c_int> {
+        if let Some(log_mask) = self.log_mask {
+            if log_mask.bits() & (1 << level) == 0 {
+                return None;
+            }
+        }
+
+        Some(match self.facility {
+            Some(facility) => Into::<libc::c_int>::into(facility) | level,
+            None => level,
+        })
+    }
+}
+
+// State shared with `SyslogAppenderHandle`.
+struct Shared {
+    settings: std::sync::RwLock<Settings>,
+    backend: Box<backend::SyslogBackend>,
+    opened: std::sync::atomic::AtomicBool,
+    open_lock: std::sync::Mutex<()>,
+    announcement: Option<String>,
+    emergency: bool,
+    // Registration of `emergency_log()` configuration, `NO_REGISTRATION` until appender is activated.
+    emergency_id: std::sync::atomic::AtomicUsize,
+    socket_paths: Option<Vec<String>>,
+}
+
+impl Shared {
+    fn open(&self, ident: Option<&str>) -> Result<(), error::SyslogError> {
+        use std::sync::atomic::Ordering;
+
+        if self.opened.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let _lock = self.open_lock
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if !self.opened.load(Ordering::Acquire) {
+            self.backend.open(ident)?;
+            self.opened.store(true, Ordering::Release);
+
+            if self.emergency {
+                let facility = self.settings().facility.unwrap_or(Facility::User);
+                let id = match self.socket_paths {
+                    Some(ref socket_paths) => emergency::register_appender(ident, facility, socket_paths),
+                    None => emergency::register_appender(ident, facility, socket::DEFAULT_SOCKET_PATHS),
+                };
+                self.emergency_id.store(id, Ordering::Release);
+            }
+
+            if let Some(ref announcement) = self.announcement {
+                let announcement = match self.backend.destination() {
+                    Some(destination) => format!("{} at {}", announcement, destination),
+                    None => announcement.clone(),
+                };
+                if let Some(priority) = self.settings().priority(libc::LOG_INFO) {
+                    let _ = self.backend.send(priority, announcement.as_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn settings(&self) -> std::sync::RwLockReadGuard<'_, Settings> {
+        self.settings
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn settings_mut(&self) -> std::sync::RwLockWriteGuard<'_, Settings> {
+        self.settings
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let id = self.emergency_id.load(std::sync::atomic::Ordering::Acquire);
+        if id != NO_REGISTRATION {
+            emergency::unregister_appender(id);
+        }
+    }
+}
+
+/// Kind of appender in configuration file which creates the same appender, see `SyslogAppender::config()`.
+#[cfg_attr(not(feature = "file"), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub(crate) enum ConfigKind {
+    LibcSyslog,
+    AutoSyslog,
+    Kmsg(Option<std::path::PathBuf>),
+}
+
 /// An appender which writes log invents into syslog using `libc`'s syslog() function.
 pub struct SyslogAppender {
     encoder: Box<log4rs::encode::Encode>,
-    level_map: Option<Box<LevelMap>>,
+    #[cfg(feature = "file")]
+    encoder_config: Option<log4rs::encode::EncoderConfig>,
+    #[cfg(feature = "file")]
+    config_kind: Option<ConfigKind>,
+    openlog_args: Option<OpenLogArgs>,
+    shared: std::sync::Arc<Shared>,
 }
 
 impl std::fmt::Debug for SyslogAppender {
@@ -61,7 +292,7 @@ impl std::fmt::Debug for SyslogAppender {
             formatter,
             "SyslogAppender {{encoder: {:?}, level_map: {}}}",
             self.encoder,
-            match self.level_map {
+            match self.shared.settings().level_map {
                 Some(_) => "Some(_)",
                 None => "None",
             }
@@ -70,47 +301,271 @@ impl std::fmt::Debug for SyslogAppender {
 }
 
 impl SyslogAppender {
+    /// Open backend. For the default backend, apply openlog() configuration of this appender.
+    ///
+    /// This is done automatically when the first message is appended. Call this explicitly to make configuration
+    /// active earlier, for example to make `emergency_log()` usable before anything is logged.
+    pub fn activate(&self) -> Result<(), error::SyslogError> {
+        self.shared.open(self.ident())
+    }
+
+    /// Describe where messages go, like path of socket picked from `SyslogAppenderBuilder::socket_paths()`. `None`
+    /// before appender is activated or if backend does not tell.
+    pub fn destination(&self) -> Option<String> {
+        if self.shared.opened.load(std::sync::atomic::Ordering::Acquire) {
+            self.shared.backend.destination()
+        } else {
+            None
+        }
+    }
+
+    fn ident(&self) -> Option<&str> {
+        self.openlog_args
+            .as_ref()
+            .map(|openlog_args| &openlog_args.ident[..])
+    }
+
+    /// Get handle for changing settings of this appender after it is passed to log4rs.
+    pub fn handle(&self) -> SyslogAppenderHandle {
+        SyslogAppenderHandle {
+            shared: std::sync::Arc::downgrade(&self.shared),
+        }
+    }
+
+    /// Get description of the appender's configuration in the format of log4rs configuration file.
+    ///
+    /// Result includes `kind` of appender (`libc-syslog`, `auto-syslog` or `kmsg`) with settings of its transport and
+    /// may be serialized and then deserialized by the deserializer registered by `register()`. Level map is always
+    /// included as a full table. Encoder is included only if appender was created from configuration file. Settings
+    /// changed through `SyslogAppenderHandle` are included, except the facility of appender without openlog()
+    /// configuration.
+    ///
+    /// Ident is exported already expanded, with `$`, `{` and `}` escaped: variables like `${VAR}` and special value
+    /// `auto` are replaced by their values at the time appender was created.
+    ///
+    /// Fails if appender uses custom backend set by `SyslogAppenderBuilder::backend()`, which can not be described
+    /// by configuration file, if its ident is literally `auto`, which would be read back as ident derived from the
+    /// name of the program, or if its level map returns something other than `libc::LOG_EMERG`..`libc::LOG_DEBUG`.
+    #[cfg(feature = "file")]
+    pub fn config(&self) -> Result<file::SyslogAppenderConfig, error::SyslogError> {
+        let levels = [
+            log::Level::Error,
+            log::Level::Warn,
+            log::Level::Info,
+            log::Level::Debug,
+            log::Level::Trace,
+        ];
+
+        let (kind, path) = match self.config_kind {
+            Some(ConfigKind::LibcSyslog) => ("libc-syslog", None),
+            Some(ConfigKind::AutoSyslog) => ("auto-syslog", None),
+            Some(ConfigKind::Kmsg(ref path)) => ("kmsg", path.clone()),
+            None => {
+                return Err(error::SyslogError::InvalidConfig {
+                    field: String::from("backend"),
+                    reason: String::from("Custom backend can not be described by configuration file"),
+                })
+            },
+        };
+
+        if self.ident() == Some("auto") {
+            return Err(error::SyslogError::InvalidConfig {
+                field: String::from("openlog.ident"),
+                reason: String::from("Literal \"auto\" can not be described by configuration file"),
+            });
+        }
+
+        let settings = self.shared.settings();
+        Ok(file::SyslogAppenderConfig {
+            kind: Some(String::from(kind)),
+            path,
+            openlog: self.openlog_args
+                .as_ref()
+                .map(|openlog_args| file::SyslogAppenderOpenlogConfig {
+                    ident: expand::escape(&openlog_args.ident),
+                    option: openlog_args.log_option,
+                    facility: settings.facility.unwrap_or(openlog_args.facility),
+                    mask: settings.log_mask,
+                }),
+            encoder: self.encoder_config
+                .as_ref()
+                .map(|encoder_config| log4rs::encode::EncoderConfig {
+                    kind: encoder_config.kind.clone(),
+                    config: encoder_config.config.clone(),
+                }),
+            level_map: Some(file::LevelMapConf(
+                levels
+                    .iter()
+                    .map(|&level| Ok((level, Severity::from_libc(settings.map_level(level)?))))
+                    .collect::<Result<_, error::SyslogError>>()?,
+            )),
+            // Mask of appender with openlog() configuration is in `openlog` section.
+            mask: match self.openlog_args {
+                Some(_) => None,
+                None => settings.log_mask,
+            },
+            sanitize: Some(settings.sanitize),
+            socket_paths: self.shared.socket_paths.clone(),
+            announce: Some(self.shared.announcement.is_some()),
+        })
+    }
+
+    /// Send message with given severity as is, without encoder and level map. Log mask, facility and sanitization
+    /// are applied.
+    ///
+    /// Does not use the thread-local buffer, so it may be called from a panic hook which interrupted `append()`.
+    pub(crate) fn append_raw(&self, severity: Severity, message: &[u8]) -> Result<(), error::SyslogError> {
+        self.shared.open(self.ident())?;
+
+        let (priority, sanitize) = {
+            let settings = self.shared.settings();
+            match settings.priority(severity.into()) {
+                Some(priority) => (priority, settings.sanitize),
+                None => return Ok(()),
+            }
+        };
+
+        if sanitize == Sanitize::Off {
+            self.shared.backend.send(priority, message)
+        } else {
+            let mut sanitized = Vec::new();
+            sanitize_message(trim_line_breaks(message), sanitize, &mut sanitized);
+            self.shared.backend.send(priority, &sanitized)
+        }
+    }
+
     /// Create new builder for `SyslogAppender`.
     pub fn builder() -> SyslogAppenderBuilder {
         SyslogAppenderBuilder {
             encoder: None,
+            #[cfg(feature = "file")]
+            encoder_config: None,
+            config_kind: Some(ConfigKind::LibcSyslog),
+            socket_paths: None,
             openlog_args: None,
+            log_mask: None,
             level_map: None,
+            sanitize: Sanitize::Off,
+            backend: None,
+            announcement: None,
+            announce: false,
+            emergency: true,
         }
     }
 }
 
 impl log4rs::append::Append for SyslogAppender {
     fn append(&self, record: &log::Record) -> std::result::Result<(), Box<std::error::Error + Sync + Send>> {
+        self.shared.open(self.ident())?;
+
+        let (priority, sanitize) = {
+            let settings = self.shared.settings();
+            match settings.priority(settings.map_level(record.level())?) {
+                Some(priority) => (priority, settings.sanitize),
+                None => return Ok(()),
+            }
+        };
+
         let mut buf = BufWriter::new();
 
-        self.encoder.encode(&mut buf, record)?;
+        self.encoder
+            .encode(&mut buf, record)
+            .map_err(error::SyslogError::Encoder)?;
 
-        let level = match self.level_map {
-            Some(ref level_map) => level_map(record.level()),
+        buf.sanitize(sanitize);
+        buf.with_message(|message| self.shared.backend.send(priority, message))?;
+        Ok(())
+    }
 
-            None => match record.level() {
-                log::Level::Error => libc::LOG_ERR,
-                log::Level::Warn => libc::LOG_WARNING,
-                log::Level::Info => libc::LOG_INFO,
-                log::Level::Debug | log::Level::Trace => libc::LOG_DEBUG,
-            },
-        };
+    fn flush(&self) {
+        let _ = self.shared.backend.flush();
+    }
+}
 
-        unsafe {
-            // This function may use the `ident` pointer previously set by `libc::openlog()`, until the call to
-            // `libc::closelog()`.
-            libc::syslog(
-                level,
-                b"%s\0".as_ptr() as *const libc::c_char,
-                buf.as_c_str(),
-            );
+impl Drop for SyslogAppender {
+    fn drop(&mut self) {
+        if self.shared.opened.load(std::sync::atomic::Ordering::Acquire) {
+            self.shared.backend.close();
+        }
+    }
+}
+
+/// Handle for changing settings of a live `SyslogAppender` without re-creating it.
+///
+/// Every change is applied atomically and affects messages appended after it. Handle does not keep appender alive:
+/// after the appender is dropped, all setters fail with `SyslogError::AppenderDropped`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log4rs_syslog;
+///
+/// let (appender, handle) = log4rs_syslog::SyslogAppender::builder()
+///     .openlog("my-daemon", log4rs_syslog::LogOption::LOG_PID, log4rs_syslog::Facility::Daemon)
+///     .build_with_handle();
+///
+/// // Pass appender to log4rs, keep handle.
+///
+/// handle
+///     .set_log_mask(Some(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Debug)))
+///     .unwrap();
+/// handle.set_sanitize(log4rs_syslog::Sanitize::Escape).unwrap();
+/// # drop(appender);
+/// ```
+#[derive(Clone)]
+pub struct SyslogAppenderHandle {
+    shared: std::sync::Weak<Shared>,
+}
+
+impl std::fmt::Debug for SyslogAppenderHandle {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("SyslogAppenderHandle")
+    }
+}
+
+impl SyslogAppenderHandle {
+    fn shared(&self) -> Result<std::sync::Arc<Shared>, error::SyslogError> {
+        self.shared.upgrade().ok_or(error::SyslogError::AppenderDropped)
+    }
+
+    /// Replace log level mapping. Fails if new mapping returns something other than
+    /// `libc::LOG_EMERG`..`libc::LOG_DEBUG`.
+    pub fn set_level_map(&self, level_map: Box<LevelMap>) -> Result<(), error::SyslogError> {
+        validate_level_map(&*level_map)?;
+
+        let shared = self.shared()?;
+        shared.settings_mut().level_map = Some(std::sync::Arc::from(level_map));
+        Ok(())
+    }
+
+    /// Replace setlogmask() mask, `None` restores the mask which was active before the first setlogmask() call.
+    ///
+    /// Mask is applied immediately if openlog() configuration of this appender is active. Fails if mask is empty.
+    pub fn set_log_mask(&self, mask: Option<LogMask>) -> Result<(), error::SyslogError> {
+        if let Some(mask) = mask {
+            validate_log_mask(mask)?;
         }
 
+        let shared = self.shared()?;
+        let mut settings = shared.settings_mut();
+        shared.backend.set_log_mask(mask);
+        settings.log_mask = mask;
+        Ok(())
+    }
+
+    /// Replace facility. It is passed with every message and overrides facility passed to openlog(). Fails for
+    /// `Facility::Kern`.
+    pub fn set_facility(&self, facility: Facility) -> Result<(), error::SyslogError> {
+        validate_facility("facility", facility)?;
+        self.shared()?.settings_mut().facility = Some(facility);
         Ok(())
     }
 
-    fn flush(&self) {}
+    /// Replace the way control characters in messages are treated.
+    pub fn set_sanitize(&self, sanitize: Sanitize) -> Result<(), error::SyslogError> {
+        self.shared()?.settings_mut().sanitize = sanitize;
+        Ok(())
+    }
 }
 
 bitflags! {
@@ -133,6 +588,54 @@ bitflags! {
     }
 }
 
+/// Lowercase name without the "LOG_" prefix, used to parse names case-insensitively.
+#[cfg(feature = "file")]
+fn normalize_name(name: &str) -> String {
+    let name = name.trim().to_ascii_lowercase();
+    match name.strip_prefix("log_") {
+        Some(name) => String::from(name),
+        None => name,
+    }
+}
+
+#[cfg(feature = "file")]
+impl LogOption {
+    const NAMES: &'static [(&'static str, LogOption)] = &[
+        ("cons", LogOption::LOG_CONS),
+        ("ndelay", LogOption::LOG_NDELAY),
+        ("nowait", LogOption::LOG_NOWAIT),
+        ("odelay", LogOption::LOG_ODELAY),
+        ("perror", LogOption::LOG_PERROR),
+        ("pid", LogOption::LOG_PID),
+    ];
+
+    fn parse_flags<E>(value: &str) -> Result<Self, E>
+    where
+        E: serde::de::Error,
+    {
+        let mut flags = LogOption::empty();
+
+        let value = value.trim();
+        if !value.is_empty() {
+            for str_flag in value.split('|') {
+                let name = normalize_name(str_flag);
+                match Self::NAMES.iter().find(|&&(flag_name, _)| flag_name == name) {
+                    Some(&(_, flag)) => flags |= flag,
+                    None => {
+                        return Err(E::custom(format!(
+                            "Unknown syslog flag: \"{}\", expected one of: LOG_CONS, LOG_NDELAY, LOG_NOWAIT, \
+                             LOG_ODELAY, LOG_PERROR, LOG_PID",
+                            str_flag.trim()
+                        )))
+                    },
+                }
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
 #[cfg(feature = "file")]
 struct LogOptionVisitor;
 
@@ -141,33 +644,65 @@ impl<'de> serde::de::Visitor<'de> for LogOptionVisitor {
     type Value = LogOption;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("list of flags separated by \"|\"")
+        formatter.write_str("list of flags separated by \"|\", sequence of flags or numeric value")
     }
 
     fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
     {
-        let mut flags = LogOption::empty();
+        LogOption::parse_flags(value)
+    }
 
-        let value = value.trim();
-        if !value.is_empty() {
-            for str_flag in value.split('|') {
-                let str_flag = str_flag.trim();
-                match str_flag {
-                    "LOG_CONS" => flags |= LogOption::LOG_CONS,
-                    "LOG_NDELAY" => flags |= LogOption::LOG_NDELAY,
-                    "LOG_NOWAIT" => flags |= LogOption::LOG_NOWAIT,
-                    "LOG_ODELAY" => flags |= LogOption::LOG_ODELAY,
-                    "LOG_PERROR" => flags |= LogOption::LOG_PERROR,
-                    "LOG_PID" => flags |= LogOption::LOG_PID,
-                    unknown => return Err(E::custom(format!("Unknown syslog flag: \"{}\"", unknown))),
-                }
-            }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut flags = LogOption::empty();
+        while let Some(str_flag) = seq.next_element::<String>()? {
+            flags |= LogOption::parse_flags(&str_flag)?;
         }
 
         Ok(flags)
     }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if value > libc::c_int::MAX as u64 {
+            return Err(E::custom(format!("Invalid syslog flags: {}", value)));
+        }
+
+        LogOption::from_bits(value as libc::c_int)
+            .ok_or_else(|| E::custom(format!("Unknown syslog flags in {:#x}", value)))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if value < 0 {
+            return Err(E::custom(format!("Invalid syslog flags: {}", value)));
+        }
+
+        self.visit_u64(value as u64)
+    }
+}
+
+#[cfg(feature = "file")]
+impl serde::ser::Serialize for LogOption {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        let flags: Vec<_> = Self::NAMES
+            .iter()
+            .filter(|&&(_, flag)| self.contains(flag))
+            .map(|&(name, _)| format!("LOG_{}", name.to_ascii_uppercase()))
+            .collect();
+        serializer.serialize_str(&flags.join(" | "))
+    }
 }
 
 #[cfg(feature = "file")]
@@ -176,12 +711,11 @@ impl<'de> serde::de::Deserialize<'de> for LogOption {
     where
         D: serde::de::Deserializer<'de>,
     {
-        deserializer.deserialize_str(LogOptionVisitor)
+        deserializer.deserialize_any(LogOptionVisitor)
     }
 }
 
-#[derive(Debug)]
-#[cfg_attr(feature = "file", derive(Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// The type of program.
 pub enum Facility {
     /// Security/authorization.
@@ -194,7 +728,8 @@ pub enum Facility {
     Daemon,
     /// FTP daemon.
     Ftp,
-    /// Kernel messages (these can't be generated from user processes).
+    /// Kernel messages (these can't be generated from user processes). Rejected by
+    /// `SyslogAppenderBuilder::try_build()`: its code is 0, which means that facility is not set.
     Kern,
     /// Reserved for local use.
     Local0,
@@ -253,81 +788,879 @@ impl Into<libc::c_int> for Facility {
     }
 }
 
-struct OpenLogArgs {
-    ident: String,
-    log_option: LogOption,
-    facility: Facility,
-}
+impl Facility {
+    const ALL: &'static [(&'static str, Facility)] = &[
+        ("auth", Facility::Auth),
+        ("authpriv", Facility::AuthPriv),
+        ("cron", Facility::Cron),
+        ("daemon", Facility::Daemon),
+        ("ftp", Facility::Ftp),
+        ("kern", Facility::Kern),
+        ("local0", Facility::Local0),
+        ("local1", Facility::Local1),
+        ("local2", Facility::Local2),
+        ("local3", Facility::Local3),
+        ("local4", Facility::Local4),
+        ("local5", Facility::Local5),
+        ("local6", Facility::Local6),
+        ("local7", Facility::Local7),
+        ("lpr", Facility::Lpr),
+        ("mail", Facility::Mail),
+        ("news", Facility::News),
+        ("syslog", Facility::Syslog),
+        ("user", Facility::User),
+        ("uucp", Facility::Uucp),
+    ];
+
+    /// Numeric code as defined in RFC 5424 (`LOG_DAEMON` is 3).
+    pub(crate) fn from_code(code: u64) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .map(|&(_, facility)| facility)
+            .find(|&facility| {
+                let value: libc::c_int = facility.into();
+                (value >> 3) as u64 == code
+            })
+    }
 
-struct IdentHolder {
-    ident: Option<String>,
+    /// Lowercase name without the "LOG_" prefix, as in syslog.conf.
+    #[cfg(any(feature = "file", feature = "testing"))]
+    pub(crate) fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|&&(_, facility)| facility == self)
+            .map(|&(name, _)| name)
+            .unwrap()
+    }
 }
 
-impl IdentHolder {
-    fn new() -> Self {
-        Self { ident: None }
+#[cfg(feature = "file")]
+impl Facility {
+    fn from_name(name: &str) -> Option<Self> {
+        let name = match &normalize_name(name)[..] {
+            // Deprecated alias from syslog.conf.
+            "security" => String::from("auth"),
+            name => name.replace('_', ""),
+        };
+
+        Self::ALL
+            .iter()
+            .find(|&&(facility_name, _)| facility_name == name)
+            .map(|&(_, facility)| facility)
     }
+}
 
-    fn openlog(&mut self, mut args: OpenLogArgs) {
-        args.ident.push('\0');
+#[cfg(feature = "file")]
+struct FacilityVisitor;
 
-        unsafe {
-            // This globally sets the `ident` pointer, which may be used by subsequent calls to the `libc::syslog()`
-            // function. Pointer should remain valid and unchanged until either call to `libc::closelog()` or call
-            // to `libc::openlog()` with new value of `ident`.
-            libc::openlog(
-                args.ident.as_ptr() as *const libc::c_char,
-                args.log_option.bits(),
-                args.facility.into(),
-            );
-        }
+#[cfg(feature = "file")]
+impl<'de> serde::de::Visitor<'de> for FacilityVisitor {
+    type Value = Facility;
 
-        // At least on Linux openlog() does not copy this string, so we should keep it available.
-        self.ident = Some(args.ident);
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("syslog facility name or numeric code")
     }
 
-    fn closelog(&mut self) {
-        if self.ident.is_some() {
-            unsafe {
-                // Among other things, this call discards the `ident` pointer set by `libc::openlog()`.
-                // After this call, `ident` may be safely dropped.
-                libc::closelog();
-            }
-        }
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Facility::from_name(value).ok_or_else(|| {
+            E::custom(format!(
+                "Unknown syslog facility: \"{}\", expected one of: {} (case-insensitive, optionally with \"LOG_\" \
+                 prefix) or numeric code",
+                value.trim(),
+                Facility::ALL
+                    .iter()
+                    .map(|&(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
     }
 
-    fn no_openlog(&mut self) {
-        self.closelog();
-        self.ident = None;
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Facility::from_code(value).ok_or_else(|| E::custom(format!("Unknown syslog facility code: {}", value)))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if value < 0 {
+            return Err(E::custom(format!("Unknown syslog facility code: {}", value)));
+        }
+
+        self.visit_u64(value as u64)
     }
 }
 
-impl Drop for IdentHolder {
-    fn drop(&mut self) {
-        // Currently this function is never used automatically because IdentHolder is created only by lazy_static.
-        self.closelog();
+#[cfg(feature = "file")]
+impl serde::ser::Serialize for Facility {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.serialize_str(&format!("LOG_{}", self.name().to_ascii_uppercase()))
     }
 }
 
-lazy_static! {
-    static ref IDENT_HOLDER: std::sync::Mutex<IdentHolder> = std::sync::Mutex::new(IdentHolder::new());
+#[cfg(feature = "file")]
+impl<'de> serde::de::Deserialize<'de> for Facility {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(FacilityVisitor)
+    }
 }
 
-/// Builder for `SyslogAppender`.
-pub struct SyslogAppenderBuilder {
-    encoder: Option<Box<log4rs::encode::Encode>>,
-    openlog_args: Option<OpenLogArgs>,
-    level_map: Option<Box<LevelMap>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Severity of the message, from the most to the least important.
+pub enum Severity {
+    /// System is unusable.
+    Emerg,
+    /// Action must be taken immediately.
+    Alert,
+    /// Critical conditions.
+    Crit,
+    /// Error conditions.
+    Err,
+    /// Warning conditions.
+    Warning,
+    /// Normal, but significant, condition.
+    Notice,
+    /// Informational message.
+    Info,
+    /// Debug-level message.
+    Debug,
 }
 
-impl SyslogAppenderBuilder {
-    /// Set custom encoder.
-    pub fn encoder(mut self, encoder: Box<log4rs::encode::Encode>) -> Self {
+impl Into<libc::c_int> for Severity {
+    fn into(self) -> libc::c_int {
+        match self {
+            Severity::Emerg => libc::LOG_EMERG,
+            Severity::Alert => libc::LOG_ALERT,
+            Severity::Crit => libc::LOG_CRIT,
+            Severity::Err => libc::LOG_ERR,
+            Severity::Warning => libc::LOG_WARNING,
+            Severity::Notice => libc::LOG_NOTICE,
+            Severity::Info => libc::LOG_INFO,
+            Severity::Debug => libc::LOG_DEBUG,
+        }
+    }
+}
+
+impl Severity {
+    const ALL: &'static [(&'static str, Severity)] = &[
+        ("emerg", Severity::Emerg),
+        ("alert", Severity::Alert),
+        ("crit", Severity::Crit),
+        ("err", Severity::Err),
+        ("warning", Severity::Warning),
+        ("notice", Severity::Notice),
+        ("info", Severity::Info),
+        ("debug", Severity::Debug),
+    ];
+
+    /// Convert libc's log level, ignoring facility bits.
+    pub(crate) fn from_libc(level: libc::c_int) -> Self {
+        Self::ALL[(level & libc::LOG_PRIMASK) as usize].1
+    }
+
+    /// Lowercase name without the "LOG_" prefix, as in syslog.conf.
+    #[cfg(any(feature = "file", feature = "testing"))]
+    pub(crate) fn name(self) -> &'static str {
+        Self::ALL[self as usize].0
+    }
+}
+
+#[cfg(feature = "file")]
+impl Severity {
+    fn from_name(name: &str) -> Option<Self> {
+        let name = match &normalize_name(name)[..] {
+            // Aliases from syslog.conf and common spellings.
+            "panic" => String::from("emerg"),
+            "critical" => String::from("crit"),
+            "error" => String::from("err"),
+            "warn" => String::from("warning"),
+            name => String::from(name),
+        };
+
+        Self::ALL
+            .iter()
+            .find(|&&(severity_name, _)| severity_name == name)
+            .map(|&(_, severity)| severity)
+    }
+
+    fn from_code(code: u64) -> Option<Self> {
+        Self::ALL.get(code as usize).map(|&(_, severity)| severity)
+    }
+}
+
+#[cfg(feature = "file")]
+impl serde::ser::Serialize for Severity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.serialize_str(&format!("LOG_{}", self.name().to_ascii_uppercase()))
+    }
+}
+
+#[cfg(feature = "file")]
+struct SeverityVisitor;
+
+#[cfg(feature = "file")]
+impl<'de> serde::de::Visitor<'de> for SeverityVisitor {
+    type Value = Severity;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("syslog severity name or numeric code from 0 to 7")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Severity::from_name(value).ok_or_else(|| {
+            E::custom(format!(
+                "Unknown syslog severity: \"{}\", expected one of: LOG_EMERG, LOG_ALERT, LOG_CRIT, LOG_ERR, \
+                 LOG_WARNING, LOG_NOTICE, LOG_INFO, LOG_DEBUG (case-insensitive, \"LOG_\" prefix is optional) or \
+                 numeric code from 0 to 7",
+                value.trim()
+            ))
+        })
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Severity::from_code(value).ok_or_else(|| {
+            E::custom(format!(
+                "Unknown syslog severity code: {}, expected number from 0 to 7",
+                value
+            ))
+        })
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if value < 0 {
+            return Err(E::custom(format!(
+                "Unknown syslog severity code: {}, expected number from 0 to 7",
+                value
+            )));
+        }
+
+        self.visit_u64(value as u64)
+    }
+}
+
+#[cfg(feature = "file")]
+impl<'de> serde::de::Deserialize<'de> for Severity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SeverityVisitor)
+    }
+}
+
+/// Mask of severities which are allowed to reach the system logger, see setlogmask(3).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogMask(libc::c_int);
+
+impl LogMask {
+    /// Allow all severities up to and including `severity`, like `LOG_UPTO()`.
+    pub fn up_to(severity: Severity) -> Self {
+        let severity: libc::c_int = severity.into();
+        LogMask((1 << (severity + 1)) - 1)
+    }
+
+    /// Allow only listed severities, like combination of `LOG_MASK()`.
+    pub fn from_severities(severities: &[Severity]) -> Self {
+        LogMask(severities.iter().fold(0, |mask, &severity| {
+            let severity: libc::c_int = severity.into();
+            mask | (1 << severity)
+        }))
+    }
+
+    /// Raw mask value for setlogmask().
+    pub fn bits(&self) -> libc::c_int {
+        self.0
+    }
+}
+
+#[cfg(feature = "file")]
+struct LogMaskVisitor;
+
+#[cfg(feature = "file")]
+impl<'de> serde::de::Visitor<'de> for LogMaskVisitor {
+    type Value = LogMask;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("maximum severity or list of severities")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(LogMask::up_to(SeverityVisitor.visit_str(value)?))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut severities = Vec::new();
+        while let Some(severity) = seq.next_element()? {
+            severities.push(severity);
+        }
+
+        Ok(LogMask::from_severities(&severities))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(LogMask::up_to(SeverityVisitor.visit_u64(value)?))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(LogMask::up_to(SeverityVisitor.visit_i64(value)?))
+    }
+}
+
+#[cfg(feature = "file")]
+impl serde::ser::Serialize for LogMask {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(None)?;
+        for &(_, severity) in Severity::ALL {
+            let level: libc::c_int = severity.into();
+            if self.0 & (1 << level) != 0 {
+                seq.serialize_element(&severity)?;
+            }
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "file")]
+impl<'de> serde::de::Deserialize<'de> for LogMask {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(LogMaskVisitor)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct OpenLogArgs {
+    ident: String,
+    log_option: LogOption,
+    facility: Facility,
+}
+
+#[derive(PartialEq)]
+struct OpenLogConfig {
+    // `ident` is terminated by '\0'.
+    args: Option<OpenLogArgs>,
+    mask: Option<LogMask>,
+}
+
+struct Registration {
+    id: usize,
+    config: OpenLogConfig,
+}
+
+const NO_REGISTRATION: usize = 0;
+
+static NEXT_REGISTRATION_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
+
+// Copy of `IdentHolder::active_id()` which may be read without locking.
+static ACTIVE_REGISTRATION_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(NO_REGISTRATION);
+
+/// Stack of openlog() configurations requested by live appenders. Configuration of the most recently registered
+/// appender is applied; when it is unregistered, the previous one is restored.
+pub(crate) struct IdentHolder {
+    registrations: Vec<Registration>,
+    opened: bool,
+    // Mask which was active before the first setlogmask() call.
+    original_mask: Option<libc::c_int>,
+}
+
+impl IdentHolder {
+    fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+            opened: false,
+            original_mask: None,
+        }
+    }
+
+    fn register(&mut self, mut config: OpenLogConfig) -> usize {
+        if let Some(ref mut args) = config.args {
+            args.ident.push('\0');
+        }
+
+        let id = NEXT_REGISTRATION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.registrations.push(Registration { id, config });
+        self.apply_active();
+        id
+    }
+
+    fn unregister(&mut self, id: usize) {
+        if let Some(pos) = self.registrations.iter().position(|registration| registration.id == id) {
+            let registration = self.registrations.remove(pos);
+            if pos == self.registrations.len() {
+                self.apply_active();
+            }
+
+            // At least on Linux openlog() does not copy `ident`, so it should be dropped only after the previous
+            // configuration is restored.
+            drop(registration);
+        }
+    }
+
+    fn active_id(&self) -> usize {
+        self.registrations
+            .last()
+            .map_or(NO_REGISTRATION, |registration| registration.id)
+    }
+
+    /// Check whether configuration requested by registration `id` is the same as the active one.
+    fn is_compatible(&self, id: usize) -> bool {
+        let config = |id| {
+            self.registrations
+                .iter()
+                .find(|registration| registration.id == id)
+                .map(|registration| &registration.config)
+        };
+
+        config(id) == config(self.active_id())
+    }
+
+    fn apply_active(&mut self) {
+        let (args, mask) = match self.registrations.last() {
+            Some(registration) => (registration.config.args.as_ref(), registration.config.mask),
+            None => (None, None),
+        };
+
+        match args {
+            Some(args) => {
+                Self::openlog(args);
+                self.opened = true;
+            },
+
+            None => if self.opened {
+                Self::closelog();
+                self.opened = false;
+            },
+        }
+
+        self.setlogmask(mask);
+
+        ACTIVE_REGISTRATION_ID.store(self.active_id(), std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn openlog(args: &OpenLogArgs) {
+        unsafe {
+            // This globally sets the `ident` pointer, which may be used by subsequent calls to the `libc::syslog()`
+            // function. Pointer should remain valid and unchanged until either call to `libc::closelog()` or call
+            // to `libc::openlog()` with new value of `ident`.
+            libc::openlog(
+                args.ident.as_ptr() as *const libc::c_char,
+                args.log_option.bits(),
+                args.facility.into(),
+            );
+        }
+    }
+
+    fn closelog() {
+        unsafe {
+            // Among other things, this call discards the `ident` pointer set by `libc::openlog()`.
+            // After this call, `ident` may be safely dropped.
+            libc::closelog();
+        }
+    }
+
+    fn setlogmask(&mut self, mask: Option<LogMask>) {
+        match mask {
+            Some(mask) => {
+                let previous = unsafe { libc::setlogmask(mask.bits()) };
+                if self.original_mask.is_none() {
+                    self.original_mask = Some(previous);
+                }
+            },
+
+            None => if let Some(original_mask) = self.original_mask.take() {
+                let _ = unsafe { libc::setlogmask(original_mask) };
+            },
+        }
+    }
+
+    fn set_mask(&mut self, id: usize, mask: Option<LogMask>) {
+        if let Some(registration) = self.registrations
+            .iter_mut()
+            .find(|registration| registration.id == id)
+        {
+            registration.config.mask = mask;
+        }
+
+        if id == self.active_id() {
+            self.setlogmask(mask);
+        }
+    }
+
+    /// Drop the connection inherited from the parent process and open a new one with the same arguments.
+    pub(crate) fn reopen(&mut self) {
+        if self.opened {
+            Self::closelog();
+            if let Some(args) = self.registrations
+                .last()
+                .and_then(|registration| registration.config.args.as_ref())
+            {
+                Self::openlog(args);
+            }
+        }
+    }
+}
+
+impl Drop for IdentHolder {
+    fn drop(&mut self) {
+        // Currently this function is never used automatically because IdentHolder is created only by lazy_static.
+        if self.opened {
+            Self::closelog();
+        }
+    }
+}
+
+lazy_static! {
+    static ref IDENT_HOLDER: std::sync::Mutex<IdentHolder> = std::sync::Mutex::new(IdentHolder::new());
+}
+
+/// Lock global openlog() state, ignoring poisoning.
+pub(crate) fn lock_ident_holder() -> std::sync::MutexGuard<'static, IdentHolder> {
+    IDENT_HOLDER
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Appenders whose backends should replace connections inherited from the parent process after fork().
+pub(crate) struct AppenderRegistry(Vec<std::sync::Weak<Shared>>);
+
+impl AppenderRegistry {
+    fn register(&mut self, shared: Shared) -> std::sync::Arc<Shared> {
+        self.0.retain(|shared| shared.strong_count() > 0);
+
+        let shared = std::sync::Arc::new(shared);
+        self.0.push(std::sync::Arc::downgrade(&shared));
+        shared
+    }
+}
+
+lazy_static! {
+    static ref APPENDERS: std::sync::Mutex<AppenderRegistry> = std::sync::Mutex::new(AppenderRegistry(Vec::new()));
+}
+
+/// Lock list of appenders, ignoring poisoning.
+pub(crate) fn lock_appenders() -> std::sync::MutexGuard<'static, AppenderRegistry> {
+    APPENDERS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Call `SyslogBackend::after_fork()` of every live appender which was activated.
+pub(crate) fn after_fork_backends() {
+    // Appender must not be dropped while list is locked: drop of libc backend locks `IDENT_HOLDER`.
+    let appenders = lock_appenders()
+        .0
+        .iter()
+        .filter_map(std::sync::Weak::upgrade)
+        .collect::<Vec<_>>();
+    for shared in appenders {
+        if shared.opened.load(std::sync::atomic::Ordering::Acquire) {
+            shared.backend.after_fork();
+        }
+    }
+}
+
+/// Registration of appender's openlog() configuration. Created on the first use of appender, removed on drop.
+struct OpenLogRegistration {
+    pending: std::sync::Mutex<Option<OpenLogConfig>>,
+    activation: std::sync::Once,
+    id: std::sync::atomic::AtomicUsize,
+    // ID of the active registration which was found to have the same configuration.
+    compatible_with: std::sync::atomic::AtomicUsize,
+    conflict_reported: std::sync::atomic::AtomicBool,
+}
+
+impl OpenLogRegistration {
+    fn new(config: OpenLogConfig) -> Self {
+        Self {
+            pending: std::sync::Mutex::new(Some(config)),
+            activation: std::sync::Once::new(),
+            id: std::sync::atomic::AtomicUsize::new(NO_REGISTRATION),
+            compatible_with: std::sync::atomic::AtomicUsize::new(NO_REGISTRATION),
+            conflict_reported: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    fn activate(&self) {
+        self.activation.call_once(|| {
+            // Lock is held until registration is complete to not miss concurrent `set_mask()`.
+            let mut pending = self.lock_pending();
+            if let Some(config) = pending.take() {
+                let id = lock_ident_holder().register(config);
+                self.id.store(id, std::sync::atomic::Ordering::Release);
+            }
+        });
+    }
+
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, Option<OpenLogConfig>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn set_mask(&self, mask: Option<LogMask>) {
+        use std::sync::atomic::Ordering;
+
+        let mut pending = self.lock_pending();
+        match *pending {
+            Some(ref mut config) => config.mask = mask,
+
+            None => {
+                let id = self.id.load(Ordering::Acquire);
+                if id != NO_REGISTRATION {
+                    lock_ident_holder().set_mask(id, mask);
+                }
+            },
+        }
+
+        // Compatibility with other registrations should be checked again.
+        self.compatible_with.store(NO_REGISTRATION, Ordering::Relaxed);
+        self.conflict_reported.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns `false` only once, when conflict with the active configuration is detected for the first time.
+    fn check_conflict(&self) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let id = self.id.load(Ordering::Acquire);
+        let active_id = ACTIVE_REGISTRATION_ID.load(Ordering::Relaxed);
+        if active_id == id || active_id == self.compatible_with.load(Ordering::Relaxed)
+            || self.conflict_reported.load(Ordering::Relaxed)
+        {
+            return true;
+        }
+
+        let ident_holder = lock_ident_holder();
+        if ident_holder.is_compatible(id) {
+            self.compatible_with
+                .store(ident_holder.active_id(), Ordering::Relaxed);
+            true
+        } else {
+            self.conflict_reported.store(true, Ordering::Relaxed);
+            false
+        }
+    }
+}
+
+impl Drop for OpenLogRegistration {
+    fn drop(&mut self) {
+        let id = self.id.load(std::sync::atomic::Ordering::Acquire);
+        if id != NO_REGISTRATION {
+            lock_ident_holder().unregister(id);
+        }
+    }
+}
+
+/// Backend which uses `libc`'s openlog(), syslog() and closelog() functions.
+struct LibcBackend {
+    openlog: OpenLogRegistration,
+}
+
+impl backend::SyslogBackend for LibcBackend {
+    fn open(&self, _ident: Option<&str>) -> Result<(), error::SyslogError> {
+        // Ident is passed to openlog() by the registration.
+        self.openlog.activate();
+        Ok(())
+    }
+
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), error::SyslogError> {
+        unsafe {
+            // This function may use the `ident` pointer previously set by `libc::openlog()`, until the call to
+            // `libc::closelog()`. Message is not required to be terminated by '\0' because of the precision.
+            libc::syslog(
+                priority,
+                b"%.*s\0".as_ptr() as *const libc::c_char,
+                message.len() as libc::c_int,
+                message.as_ptr() as *const libc::c_char,
+            );
+        }
+
+        if self.openlog.check_conflict() {
+            Ok(())
+        } else {
+            Err(error::SyslogError::OpenlogConflict)
+        }
+    }
+
+    fn flush(&self) -> Result<(), error::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {
+        // Registration is removed when appender is dropped.
+    }
+
+    fn set_log_mask(&self, mask: Option<LogMask>) {
+        self.openlog.set_mask(mask);
+    }
+}
+
+pub(crate) fn validate_ident(ident: &str) -> Result<(), error::SyslogError> {
+    let reason = if ident.is_empty() {
+        Some("Empty")
+    } else if ident.contains('\0') {
+        Some("Contains NUL character")
+    } else if ident.len() > ident::MAX_IDENT_LEN {
+        Some("Longer than 48 characters")
+    } else if !ident.bytes().all(|c| c > b' ' && c < 0x7f) {
+        Some("Contains space or characters other than printable ASCII")
+    } else {
+        None
+    };
+
+    match reason {
+        Some(reason) => Err(error::SyslogError::InvalidIdent {
+            ident: String::from(ident),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn validate_level_map(level_map: &LevelMap) -> Result<(), error::SyslogError> {
+    for level in &[
+        log::Level::Error,
+        log::Level::Warn,
+        log::Level::Info,
+        log::Level::Debug,
+        log::Level::Trace,
+    ] {
+        validate_severity(*level, level_map(*level))?;
+    }
+
+    Ok(())
+}
+
+fn validate_severity(level: log::Level, libc_level: libc::c_int) -> Result<(), error::SyslogError> {
+    if !(libc::LOG_EMERG..=libc::LOG_DEBUG).contains(&libc_level) {
+        return Err(error::SyslogError::InvalidConfig {
+            field: format!("level_map.{:?}", level),
+            reason: format!("Not a syslog severity: {}", libc_level),
+        });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_socket_paths(socket_paths: &[String]) -> Result<(), error::SyslogError> {
+    if socket_paths.is_empty() {
+        return Err(error::SyslogError::InvalidConfig {
+            field: String::from("socket_paths"),
+            reason: String::from("Empty list"),
+        });
+    }
+
+    match socket_paths.iter().position(String::is_empty) {
+        Some(pos) => Err(error::SyslogError::InvalidConfig {
+            field: format!("socket_paths[{}]", pos),
+            reason: String::from("Empty path"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// `LOG_KERN` can not be distinguished from facility which is not set, backends would silently replace it.
+pub(crate) fn validate_facility(field: &str, facility: Facility) -> Result<(), error::SyslogError> {
+    if facility == Facility::Kern {
+        Err(error::SyslogError::InvalidConfig {
+            field: String::from(field),
+            reason: String::from("LOG_KERN is reserved for kernel messages"),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_log_mask(log_mask: LogMask) -> Result<(), error::SyslogError> {
+    if log_mask.bits() == 0 {
+        Err(error::SyslogError::InvalidConfig {
+            field: String::from("openlog.mask"),
+            reason: String::from("Empty mask is ignored by setlogmask()"),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Builder for `SyslogAppender`.
+pub struct SyslogAppenderBuilder {
+    encoder: Option<Box<log4rs::encode::Encode>>,
+    #[cfg(feature = "file")]
+    encoder_config: Option<log4rs::encode::EncoderConfig>,
+    #[cfg_attr(not(feature = "file"), allow(dead_code))]
+    config_kind: Option<ConfigKind>,
+    socket_paths: Option<Vec<String>>,
+    openlog_args: Option<OpenLogArgs>,
+    log_mask: Option<LogMask>,
+    level_map: Option<Box<LevelMap>>,
+    sanitize: Sanitize,
+    backend: Option<Box<backend::SyslogBackend>>,
+    announcement: Option<String>,
+    announce: bool,
+    emergency: bool,
+}
+
+impl SyslogAppenderBuilder {
+    /// Set custom encoder.
+    pub fn encoder(mut self, encoder: Box<log4rs::encode::Encode>) -> Self {
         self.encoder = Some(encoder);
         self
     }
 
+    /// Remember configuration of encoder set by `encoder()` to include it into `SyslogAppender::config()`.
+    #[cfg(feature = "file")]
+    pub(crate) fn encoder_config(mut self, encoder_config: log4rs::encode::EncoderConfig) -> Self {
+        self.encoder_config = Some(encoder_config);
+        self
+    }
+
     /// Call openlog().
+    ///
+    /// Configuration is applied when appender is used for the first time and stays active until appender is dropped,
+    /// after that configuration of the previously activated appender which is still alive is restored.
     pub fn openlog(mut self, ident: &str, option: LogOption, facility: Facility) -> Self {
         self.openlog_args = Some(OpenLogArgs {
             ident: String::from(ident),
@@ -337,25 +1670,287 @@ impl SyslogAppenderBuilder {
         self
     }
 
-    /// Set custom log level mapping.
+    /// Call openlog() with ident derived from the name of the running program, see `auto_ident()`.
+    ///
+    /// Use `openlog()` with the `auto_ident!()` macro to fall back to the cargo package name.
+    pub fn openlog_auto(self, option: LogOption, facility: Facility) -> Self {
+        self.openlog(&ident::auto_ident(None), option, facility)
+    }
+
+    /// Call setlogmask(). Messages with severities not in mask are discarded by libc.
+    ///
+    /// When not set, mask which was active before the first setlogmask() call is restored.
+    pub fn log_mask(mut self, mask: LogMask) -> Self {
+        self.log_mask = Some(mask);
+        self
+    }
+
+    /// Set custom log level mapping. Values other than `libc::LOG_EMERG`..`libc::LOG_DEBUG` are rejected by
+    /// `try_build()`, appender built by `build()` fails to append such records.
     pub fn level_map(mut self, level_map: Box<LevelMap>) -> Self {
         self.level_map = Some(level_map);
         self
     }
 
+    /// Set the way control characters in messages are treated. Default is `Sanitize::Off`.
+    pub fn sanitize(mut self, sanitize: Sanitize) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Use custom backend instead of `libc`'s syslog().
+    ///
+    /// Configuration passed to `openlog()` is not applied with custom backend, but its ident is still passed to
+    /// `SyslogBackend::open()` and facility to `SyslogBackend::send()`.
+    pub fn backend(mut self, backend: Box<backend::SyslogBackend>) -> Self {
+        self.config_kind = None;
+        self.builtin_backend(backend)
+    }
+
+    /// Set backend which is described by kind of appender set by `config_kind()`.
+    pub(crate) fn builtin_backend(mut self, backend: Box<backend::SyslogBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Set kind of appender in configuration file for `SyslogAppender::config()`. Default is `libc-syslog`.
+    pub(crate) fn config_kind(mut self, config_kind: ConfigKind) -> Self {
+        self.config_kind = Some(config_kind);
+        self
+    }
+
+    /// Send RFC 3164 messages to the first local syslog socket from list which accepts connection, instead of using
+    /// `libc`'s syslog(). Names starting with `@` are Linux abstract-namespace names. Candidates are tried
+    /// in order on every (re)connection.
+    ///
+    /// The same list is used by `emergency_log()`. Socket which was picked is reported by
+    /// `SyslogAppender::destination()` and, if enabled by `announce()`, logged when appender is activated.
+    pub fn socket_paths<S: AsRef<str>>(mut self, paths: &[S]) -> Self {
+        if self.announcement.is_none() {
+            self.announcement = Some(String::from("log4rs-syslog: using syslog socket"));
+        }
+        self.candidate_socket_paths(paths)
+            .builtin_backend(Box::new(socket::SocketBackend::unix_candidates(paths)))
+    }
+
+    /// Remember list of local syslog sockets for validation, `emergency_log()` and `SyslogAppender::config()`
+    /// without changing backend.
+    pub(crate) fn candidate_socket_paths<S: AsRef<str>>(mut self, paths: &[S]) -> Self {
+        self.socket_paths = Some(paths.iter().map(|path| String::from(path.as_ref())).collect());
+        self
+    }
+
+    /// Log which backend is used and where messages go, with `LOG_INFO` severity, when appender is activated.
+    /// Disabled by default, enabled by `SyslogAppender::auto()`.
+    pub fn announce(mut self, enabled: bool) -> Self {
+        self.announce = enabled;
+        self
+    }
+
+    /// Set description of backend for `announce()`.
+    pub(crate) fn announcement(mut self, announcement: String) -> Self {
+        self.announcement = Some(announcement);
+        self
+    }
+
+    /// Whether activation of appender configures `emergency_log()`. Enabled by default.
+    pub(crate) fn emergency(mut self, enabled: bool) -> Self {
+        self.emergency = enabled;
+        self
+    }
+
+    /// Validate configuration, consume builder and produce `SyslogAppender`.
+    ///
+    /// Fails if ident contains characters not allowed in APP-NAME by RFC 5424 (only printable ASCII characters
+    /// except space are allowed, length is limited to 48 characters), if custom level map returns something other
+    /// than `libc::LOG_EMERG`..`libc::LOG_DEBUG`, if facility is `Facility::Kern`, if log mask is empty, or if list
+    /// of socket paths or one of paths is empty.
+    pub fn try_build(self) -> Result<SyslogAppender, error::SyslogError> {
+        if let Some(ref openlog_args) = self.openlog_args {
+            validate_ident(&openlog_args.ident)?;
+            validate_facility("openlog.facility", openlog_args.facility)?;
+        }
+
+        if let Some(ref level_map) = self.level_map {
+            validate_level_map(&**level_map)?;
+        }
+
+        if let Some(log_mask) = self.log_mask {
+            validate_log_mask(log_mask)?;
+        }
+
+        if let Some(ref socket_paths) = self.socket_paths {
+            validate_socket_paths(socket_paths)?;
+        }
+
+        Ok(self.build())
+    }
+
     /// Consume builder and produce `SyslogAppender`.
+    ///
+    /// Does not validate configuration, see `try_build()`.
     pub fn build(self) -> SyslogAppender {
-        self.openlog_args.map_or_else(
-            || IDENT_HOLDER.lock().unwrap().no_openlog(),
-            |openlog_args| IDENT_HOLDER.lock().unwrap().openlog(openlog_args),
-        );
+        let has_backend = self.backend.is_some();
+        let shared = Shared {
+            settings: std::sync::RwLock::new(Settings {
+                level_map: self.level_map.map(std::sync::Arc::from),
+                facility: self.openlog_args.as_ref().map(|openlog_args| openlog_args.facility),
+                log_mask: self.log_mask,
+                sanitize: self.sanitize,
+            }),
+            backend: match self.backend {
+                Some(backend) => backend,
+
+                None => Box::new(LibcBackend {
+                    openlog: OpenLogRegistration::new(OpenLogConfig {
+                        args: self.openlog_args.clone(),
+                        mask: self.log_mask,
+                    }),
+                }),
+            },
+            opened: std::sync::atomic::AtomicBool::new(false),
+            open_lock: std::sync::Mutex::new(()),
+            announcement: if self.announce {
+                Some(self.announcement.unwrap_or_else(|| {
+                    String::from(if has_backend {
+                        "log4rs-syslog: using custom backend"
+                    } else {
+                        "log4rs-syslog: using libc syslog()"
+                    })
+                }))
+            } else {
+                None
+            },
+            emergency: self.emergency,
+            emergency_id: std::sync::atomic::AtomicUsize::new(NO_REGISTRATION),
+            socket_paths: self.socket_paths,
+        };
 
         SyslogAppender {
             encoder: self.encoder
                 .unwrap_or_else(|| Box::new(log4rs::encode::pattern::PatternEncoder::default())),
-            level_map: self.level_map,
+            #[cfg(feature = "file")]
+            encoder_config: self.encoder_config,
+            #[cfg(feature = "file")]
+            config_kind: self.config_kind,
+            openlog_args: self.openlog_args,
+            shared: lock_appenders().register(shared),
+        }
+    }
+
+    /// Consume builder and produce `SyslogAppender` together with handle for changing its settings later.
+    ///
+    /// Does not validate configuration, see `try_build()` and `SyslogAppender::handle()`.
+    pub fn build_with_handle(self) -> (SyslogAppender, SyslogAppenderHandle) {
+        let appender = self.build();
+        let handle = appender.handle();
+        (appender, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize_message, trim_line_breaks, validate_ident, Facility, IdentHolder, LogOption, OpenLogArgs,
+                OpenLogConfig, Sanitize};
+
+    fn config(ident: &str) -> OpenLogConfig {
+        OpenLogConfig {
+            args: Some(OpenLogArgs {
+                ident: String::from(ident),
+                log_option: LogOption::empty(),
+                facility: Facility::User,
+            }),
+            mask: None,
         }
     }
+
+    #[test]
+    fn dropping_active_restores_previous() {
+        let mut ident_holder = IdentHolder::new();
+
+        let first = ident_holder.register(config("first"));
+        let second = ident_holder.register(config("second"));
+        assert_eq!(ident_holder.active_id(), second);
+
+        ident_holder.unregister(second);
+        assert_eq!(ident_holder.active_id(), first);
+        assert!(ident_holder.opened);
+
+        ident_holder.unregister(first);
+        assert!(!ident_holder.opened);
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut ident_holder = IdentHolder::new();
+
+        let first = ident_holder.register(config("ident"));
+        let second = ident_holder.register(config("ident"));
+        let third = ident_holder.register(config("other"));
+        assert!(!ident_holder.is_compatible(first));
+        assert!(!ident_holder.is_compatible(second));
+        assert!(ident_holder.is_compatible(third));
+
+        ident_holder.unregister(third);
+        assert!(ident_holder.is_compatible(first));
+    }
+
+    #[cfg(feature = "file")]
+    #[test]
+    fn parse_names() {
+        use serde::de::value::{Error, SeqDeserializer};
+        use serde::de::Visitor;
+
+        use super::{FacilityVisitor, LogOptionVisitor, Severity, SeverityVisitor};
+
+        for name in &["Daemon", "daemon", "LOG_DAEMON", "log_daemon"] {
+            assert_eq!(FacilityVisitor.visit_str::<Error>(name).unwrap(), Facility::Daemon);
+        }
+        assert_eq!(FacilityVisitor.visit_str::<Error>("auth_priv").unwrap(), Facility::AuthPriv);
+        assert_eq!(FacilityVisitor.visit_u64::<Error>(19).unwrap(), Facility::Local3);
+        assert!(FacilityVisitor.visit_str::<Error>("local8").is_err());
+        assert!(FacilityVisitor.visit_u64::<Error>(24).is_err());
+
+        assert_eq!(SeverityVisitor.visit_str::<Error>("LOG_ERR").unwrap(), Severity::Err);
+        assert_eq!(SeverityVisitor.visit_str::<Error>("error").unwrap(), Severity::Err);
+        assert_eq!(SeverityVisitor.visit_str::<Error>("Warn").unwrap(), Severity::Warning);
+        assert_eq!(SeverityVisitor.visit_u64::<Error>(7).unwrap(), Severity::Debug);
+        assert!(SeverityVisitor.visit_u64::<Error>(8).is_err());
+
+        assert_eq!(
+            LogOptionVisitor.visit_str::<Error>("LOG_PID | ndelay").unwrap(),
+            LogOption::LOG_PID | LogOption::LOG_NDELAY
+        );
+        assert_eq!(
+            LogOptionVisitor
+                .visit_seq(SeqDeserializer::<_, Error>::new(vec!["pid", "LOG_CONS"].into_iter()))
+                .unwrap(),
+            LogOption::LOG_PID | LogOption::LOG_CONS
+        );
+        assert!(LogOptionVisitor.visit_str::<Error>("LOG_PIDS").is_err());
+    }
+
+    #[test]
+    fn sanitization() {
+        let sanitize = |message: &[u8], sanitize| {
+            let mut sanitized = Vec::new();
+            sanitize_message(trim_line_breaks(message), sanitize, &mut sanitized);
+            sanitized
+        };
+
+        assert_eq!(&sanitize(b"a\tb\r\nc\0\x1b\n\n", Sanitize::Escape)[..], &b"a\tb\\r\\nc\\0\\x1b"[..]);
+        assert_eq!(&sanitize(b"a\tb\r\nc\0\x1b\n\n", Sanitize::Replace)[..], &b"a\tb  c  "[..]);
+        assert_eq!(&sanitize(b"\n", Sanitize::Escape)[..], &b""[..]);
+    }
+
+    #[test]
+    fn ident_validation() {
+        assert!(validate_ident("my-daemon").is_ok());
+        assert!(validate_ident("").is_err());
+        assert!(validate_ident("my\0daemon").is_err());
+        assert!(validate_ident("my daemon").is_err());
+        assert!(validate_ident(&"x".repeat(49)).is_err());
+    }
 }
 
 #[cfg(all(feature = "unstable", test))]
@@ -368,7 +1963,7 @@ mod bench {
         bencher.iter(|| {
             let mut buf = super::BufWriter::new();
             buf.write_all(data).unwrap();
-            buf.as_c_str()
+            buf.with_message(|message| message.len())
         })
     }
 
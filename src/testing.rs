@@ -0,0 +1,242 @@
+//! Capturing backend and assertion macros for testing of logging code.
+//!
+//! Macros `assert_logged!()`, `assert_not_logged!()` and `assert_snapshot!()` are exported from the crate root.
+
+#![forbid(unsafe_code)]
+
+use std;
+
+use backend;
+use error;
+use libc;
+use syslog;
+
+/// Message recorded by `CaptureBackend`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedMessage {
+    /// Facility. `Facility::User` if appender has no openlog() configuration, like libc does.
+    pub facility: syslog::Facility,
+    /// Severity after level mapping.
+    pub severity: syslog::Severity,
+    /// Ident passed to `SyslogAppenderBuilder::openlog()`, if any.
+    pub ident: Option<String>,
+    /// Message as produced by encoder, after sanitization.
+    pub message: Vec<u8>,
+}
+
+impl CapturedMessage {
+    /// Message as text without trailing line breaks. Invalid UTF-8 sequences are replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.message)
+            .trim_end_matches(&['\r', '\n'][..])
+            .to_string()
+    }
+}
+
+/// Formats message as a snapshot line: `facility.severity ident: text`, control characters in text are escaped.
+impl std::fmt::Display for CapturedMessage {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{}.{}", self.facility.name(), self.severity.name())?;
+        if let Some(ref ident) = self.ident {
+            write!(formatter, " {}", ident)?;
+        }
+        formatter.write_str(":")?;
+
+        let text = self.text();
+        if !text.is_empty() {
+            formatter.write_str(" ")?;
+        }
+        for c in text.chars() {
+            if c.is_control() && c != '\t' {
+                write!(formatter, "{}", c.escape_default())?;
+            } else {
+                write!(formatter, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct CaptureState {
+    ident: Option<String>,
+    messages: Vec<CapturedMessage>,
+}
+
+/// Backend which records messages in memory instead of sending them, for testing of logging code.
+///
+/// Clones share the same records: pass one clone to `SyslogAppenderBuilder::backend()`, keep another one for checks
+/// with `assert_logged!()`, `assert_not_logged!()` and `assert_snapshot!()`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log;
+/// extern crate log4rs;
+/// #[macro_use]
+/// extern crate log4rs_syslog;
+///
+/// use log4rs::append::Append;
+///
+/// # fn main() {
+/// let capture = log4rs_syslog::testing::CaptureBackend::new();
+/// let appender = log4rs_syslog::SyslogAppender::builder()
+///     .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+///     .openlog("my-daemon", log4rs_syslog::LogOption::LOG_PID, log4rs_syslog::Facility::Daemon)
+///     .backend(Box::new(capture.clone()))
+///     .build();
+///
+/// appender
+///     .append(&log::Record::builder()
+///         .level(log::Level::Error)
+///         .args(format_args!("connection lost"))
+///         .build())
+///     .unwrap();
+///
+/// assert_logged!(capture, log4rs_syslog::Severity::Err, "connection lost");
+/// assert_snapshot!(capture, "daemon.err my-daemon: connection lost");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct CaptureBackend {
+    state: std::sync::Arc<std::sync::Mutex<CaptureState>>,
+}
+
+impl std::fmt::Debug for CaptureBackend {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("CaptureBackend")
+            .field("messages", &self.lock().messages)
+            .finish()
+    }
+}
+
+impl CaptureBackend {
+    /// Create backend without recorded messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CaptureState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Get copy of all recorded messages, oldest first.
+    pub fn messages(&self) -> Vec<CapturedMessage> {
+        self.lock().messages.clone()
+    }
+
+    /// Forget all recorded messages.
+    pub fn clear(&self) {
+        self.lock().messages.clear();
+    }
+
+    /// Check whether message with given severity containing `text` was recorded.
+    pub fn contains(&self, severity: syslog::Severity, text: &str) -> bool {
+        self.lock()
+            .messages
+            .iter()
+            .any(|message| message.severity == severity && message.text().contains(text))
+    }
+
+    /// Format all recorded messages, one per line, see `Display` implementation of `CapturedMessage`.
+    pub fn snapshot(&self) -> String {
+        self.lock()
+            .messages
+            .iter()
+            .map(|message| message.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl backend::SyslogBackend for CaptureBackend {
+    fn open(&self, ident: Option<&str>) -> Result<(), error::SyslogError> {
+        self.lock().ident = ident.map(String::from);
+        Ok(())
+    }
+
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), error::SyslogError> {
+        let mut state = self.lock();
+        let message = CapturedMessage {
+            facility: match priority & libc::LOG_FACMASK {
+                // Not configured (`LOG_KERN` is rejected), libc uses its default.
+                0 => syslog::Facility::User,
+                facility => syslog::Facility::from_code((facility >> 3) as u64).unwrap_or(syslog::Facility::User),
+            },
+            severity: syslog::Severity::from_libc(priority),
+            ident: state.ident.clone(),
+            message: message.to_vec(),
+        };
+        state.messages.push(message);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), error::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {}
+}
+
+/// Prepare expected snapshot for comparison: strip indentation and trailing spaces, drop empty lines.
+pub fn normalize_snapshot(snapshot: &str) -> String {
+    snapshot
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Assert that `CaptureBackend` recorded message with given severity containing given text.
+#[macro_export]
+macro_rules! assert_logged {
+    ($capture:expr, $severity:expr, $text:expr) => {{
+        let (capture, severity, text) = (&$capture, $severity, $text);
+        if !capture.contains(severity, text) {
+            panic!(
+                "assertion failed: no {:?} message containing {:?}, recorded messages:\n{}",
+                severity,
+                text,
+                capture.snapshot()
+            );
+        }
+    }};
+}
+
+/// Assert that `CaptureBackend` did not record message with given severity containing given text.
+#[macro_export]
+macro_rules! assert_not_logged {
+    ($capture:expr, $severity:expr, $text:expr) => {{
+        let (capture, severity, text) = (&$capture, $severity, $text);
+        if capture.contains(severity, text) {
+            panic!(
+                "assertion failed: unexpected {:?} message containing {:?}, recorded messages:\n{}",
+                severity,
+                text,
+                capture.snapshot()
+            );
+        }
+    }};
+}
+
+/// Assert that all messages recorded by `CaptureBackend` match snapshot, see `CaptureBackend::snapshot()`.
+///
+/// Indentation and empty lines in expected snapshot are ignored, so it may be written as an indented multiline
+/// string literal.
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($capture:expr, $expected:expr) => {{
+        let actual = $crate::testing::normalize_snapshot(&$capture.snapshot());
+        let expected = $crate::testing::normalize_snapshot($expected);
+        if actual != expected {
+            panic!(
+                "assertion failed: snapshot mismatch\n--- expected:\n{}\n--- recorded:\n{}",
+                expected, actual
+            );
+        }
+    }};
+}
@@ -0,0 +1,212 @@
+extern crate libc;
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Captured {
+    opened: usize,
+    closed: usize,
+    messages: Vec<(libc::c_int, Vec<u8>)>,
+}
+
+struct MemoryBackend(Arc<Mutex<Captured>>);
+
+impl log4rs_syslog::SyslogBackend for MemoryBackend {
+    fn open(&self, _ident: Option<&str>) -> Result<(), log4rs_syslog::SyslogError> {
+        self.0.lock().unwrap().opened += 1;
+        Ok(())
+    }
+
+    fn send(&self, priority: libc::c_int, message: &[u8]) -> Result<(), log4rs_syslog::SyslogError> {
+        self.0.lock().unwrap().messages.push((priority, message.to_vec()));
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), log4rs_syslog::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {
+        self.0.lock().unwrap().closed += 1;
+    }
+}
+
+#[test]
+fn custom_backend() {
+    let captured = Arc::new(Mutex::new(Captured::default()));
+
+    let (appender, handle) = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}{n}")))
+        .openlog(
+            "memory",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Local1,
+        )
+        .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Info))
+        .sanitize(log4rs_syslog::Sanitize::Escape)
+        .backend(Box::new(MemoryBackend(captured.clone())))
+        .build_with_handle();
+
+    support::append(&appender, log::Level::Warn, "first\nline");
+    support::append(&appender, log::Level::Debug, "masked");
+    handle.set_sanitize(log4rs_syslog::Sanitize::Off).unwrap();
+    support::append(&appender, log::Level::Error, "second");
+    drop(appender);
+
+    let captured = captured.lock().unwrap();
+    assert_eq!(captured.opened, 1);
+    assert_eq!(captured.closed, 1);
+    assert_eq!(
+        captured.messages,
+        vec![
+            (libc::LOG_LOCAL1 | libc::LOG_WARNING, b"first\\nline".to_vec()),
+            (libc::LOG_LOCAL1 | libc::LOG_ERR, b"second\n".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn invalid_level_map() {
+    let captured = Arc::new(Mutex::new(Captured::default()));
+
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+        .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Debug))
+        .level_map(Box::new(|_| 40))
+        .backend(Box::new(MemoryBackend(captured.clone())))
+        .build();
+
+    let err = log4rs::append::Append::append(
+        &appender,
+        &log::Record::builder()
+            .level(log::Level::Error)
+            .args(format_args!("out of range"))
+            .build(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        log4rs_syslog::SyslogError::InvalidConfig {
+            field: String::from("level_map.Error"),
+            reason: String::from("Not a syslog severity: 40"),
+        }
+        .to_string()
+    );
+    assert!(captured.lock().unwrap().messages.is_empty());
+}
+
+#[test]
+fn kern_facility() {
+    let err = log4rs_syslog::SyslogAppender::builder()
+        .openlog("kern", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Kern)
+        .backend(Box::new(MemoryBackend(Arc::default())))
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("\"openlog.facility\""), "{}", err);
+
+    let (_appender, handle) = log4rs_syslog::SyslogAppender::builder()
+        .backend(Box::new(MemoryBackend(Arc::default())))
+        .build_with_handle();
+    assert!(handle.set_facility(log4rs_syslog::Facility::Kern).is_err());
+    handle.set_facility(log4rs_syslog::Facility::Daemon).unwrap();
+}
+
+struct RateLimitedBackend;
+
+impl log4rs_syslog::SyslogBackend for RateLimitedBackend {
+    fn open(&self, _ident: Option<&str>) -> Result<(), log4rs_syslog::SyslogError> {
+        Ok(())
+    }
+
+    fn send(&self, _priority: libc::c_int, _message: &[u8]) -> Result<(), log4rs_syslog::SyslogError> {
+        Err(log4rs_syslog::SyslogError::RateLimited)
+    }
+
+    fn flush(&self) -> Result<(), log4rs_syslog::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {}
+}
+
+#[test]
+fn backend_error() {
+    use log4rs::append::Append;
+
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .backend(Box::new(RateLimitedBackend))
+        .build();
+    let err = appender
+        .append(&log::Record::builder().args(format_args!("dropped")).build())
+        .unwrap_err();
+    let err = err.downcast_ref::<log4rs_syslog::SyslogError>().unwrap();
+    assert!(err.is_dropped_message());
+    assert!(!err.is_config_error());
+}
+
+#[test]
+fn journald_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("socket");
+    let journald = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}{n}")))
+        .openlog(
+            "journald-test",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Daemon,
+        )
+        .backend(Box::new(log4rs_syslog::JournaldBackend::with_path(&path)))
+        .build();
+
+    let mut buf = vec![0; 4096];
+
+    support::append(&appender, log::Level::Warn, "single line");
+    let len = journald.recv(&mut buf).unwrap();
+    assert_eq!(
+        &buf[..len],
+        &b"PRIORITY=4\nSYSLOG_FACILITY=3\nSYSLOG_IDENTIFIER=journald-test\nMESSAGE=single line\n"[..]
+    );
+
+    support::append(&appender, log::Level::Error, "first\nsecond");
+    let len = journald.recv(&mut buf).unwrap();
+    assert_eq!(
+        &buf[..len],
+        &b"PRIORITY=3\nSYSLOG_FACILITY=3\nSYSLOG_IDENTIFIER=journald-test\nMESSAGE\n\x0c\0\0\0\0\0\0\0first\nsecond\n"[..]
+    );
+}
+
+#[test]
+fn kmsg_backend() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}{n}")))
+        .backend(Box::new(log4rs_syslog::KmsgBackend::with_path(file.path())))
+        .build();
+    support::append(&appender, log::Level::Info, "no facility");
+
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+        .openlog(
+            "kmsg-test",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Daemon,
+        )
+        .backend(Box::new(log4rs_syslog::KmsgBackend::with_path(file.path())))
+        .build();
+    support::append(&appender, log::Level::Error, &"\u{fc}".repeat(1000));
+
+    let written = std::fs::read_to_string(file.path()).unwrap();
+    let (first, second) = written.split_at(written.find("<27>").unwrap());
+    assert!(first.starts_with("<14>") && first.ends_with(": no facility"), "{}", first);
+    assert!(second.starts_with("<27>kmsg-test: \u{fc}\u{fc}"), "{}", second);
+    assert!(second.len() <= 992 && second.len() > 990, "{}", second.len());
+}
@@ -0,0 +1,472 @@
+extern crate jsonschema;
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate serde_json;
+extern crate serde_value;
+extern crate serde_yaml;
+extern crate tempfile;
+
+fn deserialize_kind(
+    kind: &str,
+    yaml: &str,
+) -> Result<Box<log4rs::append::Append>, Box<std::error::Error + Sync + Send>> {
+    let mut deserializers = log4rs::file::Deserializers::new();
+    log4rs_syslog::register(&mut deserializers);
+
+    let config: serde_value::Value = serde_yaml::from_str(yaml).unwrap();
+    deserializers.deserialize(kind, config)
+}
+
+fn deserialize(yaml: &str) -> Result<Box<log4rs::append::Append>, Box<std::error::Error + Sync + Send>> {
+    deserialize_kind("libc-syslog", yaml)
+}
+
+#[test]
+fn config_round_trip() {
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog(
+            "round-trip",
+            log4rs_syslog::LogOption::LOG_PID | log4rs_syslog::LogOption::LOG_NDELAY,
+            log4rs_syslog::Facility::Local3,
+        )
+        .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Info))
+        .sanitize(log4rs_syslog::Sanitize::Escape)
+        .socket_paths(&["/dev/log", "@rsyslog"])
+        .build();
+
+    let yaml = serde_yaml::to_string(&appender.config().unwrap()).unwrap();
+    assert!(yaml.contains("ident: round-trip"), "{}", yaml);
+    assert!(yaml.contains("facility: LOG_LOCAL3"), "{}", yaml);
+    assert!(yaml.contains("TRACE: LOG_DEBUG"), "{}", yaml);
+    assert!(yaml.contains("sanitize: escape"), "{}", yaml);
+    assert!(yaml.contains("- \"@rsyslog\""), "{}", yaml);
+
+    let _ = deserialize(&yaml).unwrap();
+
+    let parsed: log4rs_syslog::SyslogAppenderConfig = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(serde_yaml::to_string(&parsed).unwrap(), yaml);
+}
+
+// Check that configuration exported by `SyslogAppender::config()` is accepted by deserializer of its `kind` and
+// survives parsing unchanged.
+fn assert_round_trip(
+    appender: &log4rs_syslog::SyslogAppender,
+    kind: &str,
+) -> (String, Box<log4rs::append::Append>) {
+    let yaml = serde_yaml::to_string(&appender.config().unwrap()).unwrap();
+    assert!(yaml.contains(&format!("kind: {}\n", kind)), "{}", yaml);
+
+    let restored = deserialize_kind(kind, &yaml).unwrap();
+
+    let parsed: log4rs_syslog::SyslogAppenderConfig = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(serde_yaml::to_string(&parsed).unwrap(), yaml);
+
+    (yaml, restored)
+}
+
+#[test]
+fn config_round_trip_libc_syslog() {
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Notice))
+        .build();
+
+    let (yaml, _) = assert_round_trip(&appender, "libc-syslog");
+    assert!(!yaml.contains("openlog:"), "{}", yaml);
+    assert!(yaml.contains("- LOG_NOTICE"), "{}", yaml);
+    assert!(!yaml.contains("socket_paths:"), "{}", yaml);
+}
+
+#[test]
+fn config_round_trip_socket() {
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog("socket", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+        .socket_paths(&["/dev/log", "@rsyslog"])
+        .announce(true)
+        .build();
+
+    let (yaml, _) = assert_round_trip(&appender, "libc-syslog");
+    assert!(yaml.contains("- \"@rsyslog\""), "{}", yaml);
+    assert!(yaml.contains("announce: true"), "{}", yaml);
+}
+
+#[test]
+fn config_round_trip_auto_syslog() {
+    let appender = log4rs_syslog::SyslogAppender::auto_with_socket_paths(&["/nonexistent/log"])
+        .openlog("auto-test", log4rs_syslog::LogOption::LOG_PID, log4rs_syslog::Facility::Local1)
+        .build();
+
+    let (yaml, _) = assert_round_trip(&appender, "auto-syslog");
+    assert!(yaml.contains("- /nonexistent/log"), "{}", yaml);
+}
+
+#[test]
+fn config_round_trip_kmsg() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let appender = log4rs_syslog::SyslogAppender::kmsg_with_path(file.path())
+        .openlog("kmsg-trip", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Local0)
+        .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Info))
+        .build();
+
+    let (yaml, restored) = assert_round_trip(&appender, "kmsg");
+    assert!(yaml.contains(&format!("path: {}", file.path().display())), "{}", yaml);
+    assert!(yaml.contains("- LOG_INFO"), "{}", yaml);
+
+    // Restored appender writes into the same file.
+    restored
+        .append(
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .args(format_args!("restored"))
+                .build(),
+        )
+        .unwrap();
+    let written = std::fs::read_to_string(file.path()).unwrap();
+    assert!(written.starts_with("<134>kmsg-trip: "), "{}", written);
+    assert!(written.ends_with(" restored"), "{}", written);
+
+    let (yaml, _) = assert_round_trip(&log4rs_syslog::SyslogAppender::kmsg().build(), "kmsg");
+    assert!(!yaml.contains("path:"), "{}", yaml);
+}
+
+#[test]
+fn config_round_trip_ident() {
+    // Derived ident is exported as is.
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog_auto(log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+        .build();
+    let (yaml, _) = assert_round_trip(&appender, "libc-syslog");
+    assert!(yaml.contains(&format!("ident: {}\n", log4rs_syslog::auto_ident(None))), "{}", yaml);
+    assert!(!yaml.contains("ident: auto\n"), "{}", yaml);
+
+    // Characters used by templates are escaped.
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog("x{pid}$y", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+        .build();
+    let (yaml, _) = assert_round_trip(&appender, "libc-syslog");
+    assert!(yaml.contains("x{{pid}}$$y"), "{}", yaml);
+
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog("auto", log4rs_syslog::LogOption::empty(), log4rs_syslog::Facility::Daemon)
+        .build();
+    let err = appender.config().unwrap_err();
+    assert!(err.to_string().contains("\"openlog.ident\""), "{}", err);
+}
+
+#[test]
+fn config_custom_backend() {
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .backend(Box::new(log4rs_syslog::KmsgBackend::new()))
+        .build();
+
+    let err = appender.config().unwrap_err();
+    assert!(err.to_string().contains("\"backend\""), "{}", err);
+}
+
+#[test]
+fn invalid_mask_and_path() {
+    let err = deserialize("mask: LOG_ERR\nopenlog:\n  ident: x\n  option: LOG_PID\n  facility: user\n  mask: LOG_ERR\n")
+        .unwrap_err();
+    assert!(err.to_string().contains("\"mask\""), "{}", err);
+
+    let err = deserialize("path: /dev/kmsg\n").unwrap_err();
+    assert!(err.to_string().contains("\"path\""), "{}", err);
+
+    let err = deserialize_kind("auto-syslog", "path: /dev/kmsg\n").unwrap_err();
+    assert!(err.to_string().contains("\"path\""), "{}", err);
+}
+
+#[test]
+fn invalid_socket_paths() {
+    let err = deserialize("socket_paths: []\n").unwrap_err();
+    assert!(err.to_string().contains("\"socket_paths\""), "{}", err);
+
+    let err = deserialize("socket_paths: ['/dev/log', '']\n").unwrap_err();
+    assert!(err.to_string().contains("\"socket_paths[1]\""), "{}", err);
+}
+
+#[test]
+fn config_schema() {
+    let schema: serde_json::Value = serde_json::from_str(log4rs_syslog::config_schema()).unwrap();
+
+    for kind in &["libc_syslog", "auto_syslog", "kmsg"] {
+        let properties = schema["definitions"][*kind]["properties"].as_object().unwrap();
+        for key in &["kind", "filters", "openlog", "encoder", "level_map", "mask", "sanitize", "announce"] {
+            assert!(properties.contains_key(*key), "Missing {} property: {}", kind, key);
+        }
+    }
+    assert!(schema["definitions"]["kmsg"]["properties"]["path"].is_object());
+
+    let openlog = schema["definitions"]["openlog"]["properties"].as_object().unwrap();
+    for key in &["ident", "option", "facility", "mask"] {
+        assert!(openlog.contains_key(*key), "Missing openlog property: {}", key);
+    }
+}
+
+fn validate(schema: &jsonschema::JSONSchema, yaml: &str) -> Result<(), String> {
+    let config: serde_json::Value = serde_yaml::from_str(yaml).unwrap();
+    schema
+        .validate(&config)
+        .map_err(|errors| errors.map(|err| err.to_string()).collect::<Vec<_>>().join("; "))
+}
+
+// Appender configurations from `appenders` sections of YAML examples in README and `./examples/`.
+fn example_configs() -> Vec<String> {
+    let mut sources = vec![std::fs::read_to_string("README.md").unwrap()];
+    for entry in std::fs::read_dir("examples").unwrap() {
+        sources.push(std::fs::read_to_string(entry.unwrap().path()).unwrap());
+    }
+
+    let mut configs = Vec::new();
+    for source in &sources {
+        let blocks = source
+            .split("```yaml\n")
+            .skip(1)
+            .map(|block| block.split("```").next().unwrap())
+            .chain(
+                source
+                    .split("br#\"")
+                    .skip(1)
+                    .map(|block| block.split("\"#").next().unwrap()),
+            );
+        for block in blocks {
+            let conf: serde_yaml::Value = serde_yaml::from_str(block).unwrap();
+            for (_, appender) in conf["appenders"].as_mapping().unwrap() {
+                configs.push(serde_yaml::to_string(appender).unwrap());
+            }
+        }
+    }
+    configs
+}
+
+#[test]
+fn config_schema_validation() {
+    let schema: serde_json::Value = serde_json::from_str(log4rs_syslog::config_schema()).unwrap();
+    let schema = jsonschema::JSONSchema::options()
+        .with_draft(jsonschema::Draft::Draft7)
+        .compile(&schema)
+        .unwrap();
+
+    let examples = example_configs();
+    assert!(examples.len() >= 2, "{:?}", examples);
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let exported = [
+        log4rs_syslog::SyslogAppender::builder()
+            .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Info))
+            .build(),
+        log4rs_syslog::SyslogAppender::builder()
+            .openlog("schema", log4rs_syslog::LogOption::LOG_PID, log4rs_syslog::Facility::Daemon)
+            .log_mask(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Info))
+            .socket_paths(&["/dev/log", "@rsyslog"])
+            .build(),
+        log4rs_syslog::SyslogAppender::auto_with_socket_paths(&["/nonexistent/log"]).build(),
+        log4rs_syslog::SyslogAppender::kmsg_with_path(file.path()).build(),
+    ].iter()
+        .map(|appender| serde_yaml::to_string(&appender.config().unwrap()).unwrap())
+        .collect::<Vec<_>>();
+
+    let valid = [
+        "kind: auto-syslog\nsocket_paths: ['/dev/log', '@rsyslog']\nannounce: false\n",
+        "kind: kmsg\npath: /dev/kmsg\nmask: [LOG_ERR, LOG_INFO]\nsanitize: escape\n",
+        "
+kind: libc-syslog
+encoder:
+  kind: sd-daemon
+  encoder:
+    kind: pattern
+    pattern: '{m}'
+  level_map:
+    Trace: LOG_DEBUG
+  facility: daemon
+",
+    ];
+    for yaml in examples.iter().chain(&exported).map(String::as_str).chain(valid.iter().cloned()) {
+        if let Err(err) = validate(&schema, yaml) {
+            panic!("{}\n{}", err, yaml);
+        }
+        // Everything accepted by schema is accepted by deserializers too.
+        let kind: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        let _ = deserialize_kind(kind["kind"].as_str().unwrap(), yaml).unwrap();
+    }
+
+    let invalid = [
+        "socket_paths: ['/dev/log']\n",
+        "kind: syslog\n",
+        "kind: libc-syslog\nmask: []\n",
+        "kind: libc-syslog\nopenlog: {ident: x, option: 0, facility: user, mask: []}\n",
+        "kind: libc-syslog\npath: /dev/kmsg\n",
+        "kind: kmsg\nsocket_paths: ['/dev/log']\n",
+        "kind: auto-syslog\nunknown: 1\n",
+        "kind: libc-syslog\nencoder: {kind: sd-daemon, pattern: '{m}'}\n",
+        "kind: libc-syslog\nencoder: {kind: sd-daemon, facility: nowhere}\n",
+        "kind: libc-syslog\nopenlog: {ident: x, option: 0, facility: LOG_KERN}\n",
+        "kind: libc-syslog\nopenlog: {ident: x, option: 0, facility: 0}\n",
+    ];
+    for yaml in &invalid {
+        assert!(validate(&schema, yaml).is_err(), "{}", yaml);
+    }
+}
+
+#[test]
+fn handle_changes_config() {
+    let (appender, handle) = log4rs_syslog::SyslogAppender::builder()
+        .openlog(
+            "handle",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::User,
+        )
+        .build_with_handle();
+
+    handle.set_facility(log4rs_syslog::Facility::Local5).unwrap();
+    handle
+        .set_log_mask(Some(log4rs_syslog::LogMask::up_to(log4rs_syslog::Severity::Err)))
+        .unwrap();
+    handle
+        .set_level_map(Box::new(|_| log4rs_syslog::Severity::Notice.into()))
+        .unwrap();
+    handle.set_sanitize(log4rs_syslog::Sanitize::Replace).unwrap();
+    assert!(handle.set_level_map(Box::new(|_| 8)).is_err());
+
+    let yaml = serde_yaml::to_string(&appender.config().unwrap()).unwrap();
+    assert!(yaml.contains("facility: LOG_LOCAL5"), "{}", yaml);
+    assert!(yaml.contains("- LOG_ERR"), "{}", yaml);
+    assert!(!yaml.contains("- LOG_WARNING"), "{}", yaml);
+    assert!(yaml.contains("TRACE: LOG_NOTICE"), "{}", yaml);
+    assert!(yaml.contains("sanitize: replace"), "{}", yaml);
+
+    drop(appender);
+    assert!(handle.set_sanitize(log4rs_syslog::Sanitize::Off).is_err());
+}
+
+#[test]
+fn partial_level_map() {
+    let appender = deserialize("level_map:\n  Trace: 5\n  error: LOG_CRIT\n").unwrap();
+    drop(appender);
+
+    let err = deserialize("level_map:\n  Fatal: LOG_CRIT\n").unwrap_err();
+    assert!(err.to_string().contains("Unknown log level"), "{}", err);
+
+    let err = deserialize("level_map:\n  Error: LOG_CRIT\n  ERROR: LOG_ERR\n").unwrap_err();
+    assert!(err.to_string().contains("Duplicate log level"), "{}", err);
+
+    let err = deserialize("level_map:\n  Error: 8\n").unwrap_err();
+    assert!(err.to_string().contains("Unknown syslog severity"), "{}", err);
+}
+
+struct Buf(Vec<u8>);
+
+impl std::io::Write for Buf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl log4rs::encode::Write for Buf {}
+
+fn encode(encoder: &log4rs::encode::Encode, level: log::Level, message: &str) -> String {
+    let mut buf = Buf(Vec::new());
+    encoder
+        .encode(
+            &mut buf,
+            &log::Record::builder()
+                .level(level)
+                .args(format_args!("{}", message))
+                .build(),
+        )
+        .unwrap();
+    String::from_utf8(buf.0).unwrap()
+}
+
+#[test]
+fn sd_daemon_encoder() {
+    let mut deserializers = log4rs::file::Deserializers::new();
+    log4rs_syslog::register(&mut deserializers);
+
+    let config: serde_value::Value = serde_yaml::from_str(
+        "
+encoder:
+  kind: pattern
+  pattern: '{m}'
+level_map:
+  Error: LOG_CRIT
+",
+    ).unwrap();
+    let encoder: Box<log4rs::encode::Encode> = deserializers.deserialize("sd-daemon", config).unwrap();
+    assert_eq!(encode(&*encoder, log::Level::Error, "failed"), "<2>failed");
+    assert_eq!(encode(&*encoder, log::Level::Warn, "degraded"), "<4>degraded");
+    assert_eq!(encode(&*encoder, log::Level::Trace, "details"), "<7>details");
+
+    let config: serde_value::Value = serde_yaml::from_str("facility: daemon").unwrap();
+    let encoder: Box<log4rs::encode::Encode> = deserializers.deserialize("sd-daemon", config).unwrap();
+    let encoded = encode(&*encoder, log::Level::Info, "started");
+    assert!(encoded.starts_with("<30>") && encoded.ends_with("started\n"), "{}", encoded);
+
+    let config: serde_value::Value = serde_yaml::from_str("level_map: {Fatal: LOG_ERR}").unwrap();
+    assert!(deserializers
+        .deserialize::<log4rs::encode::Encode>("sd-daemon", config)
+        .is_err());
+}
+
+#[test]
+fn auto_syslog() {
+    let mut deserializers = log4rs::file::Deserializers::new();
+    log4rs_syslog::register(&mut deserializers);
+
+    let config: serde_value::Value = serde_yaml::from_str(
+        "
+openlog:
+  ident: auto-test
+  option: LOG_PID
+  facility: daemon
+sanitize: escape
+",
+    ).unwrap();
+    let _: Box<log4rs::append::Append> = deserializers.deserialize("auto-syslog", config).unwrap();
+
+    let config: serde_value::Value =
+        serde_yaml::from_str("openlog: {ident: 'bad ident', option: 0, facility: user}").unwrap();
+    assert!(deserializers
+        .deserialize::<log4rs::append::Append>("auto-syslog", config)
+        .is_err());
+}
+
+#[test]
+fn kmsg() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let appender = deserialize_kind(
+        "kmsg",
+        &format!(
+            "
+path: {:?}
+openlog:
+  ident: kmsg-conf
+  option: 0
+  facility: local0
+encoder:
+  kind: pattern
+  pattern: '{{m}}'
+level_map:
+  Warn: LOG_NOTICE
+",
+            file.path()
+        ),
+    ).unwrap();
+
+    appender
+        .append(
+            &log::Record::builder()
+                .level(log::Level::Warn)
+                .args(format_args!("from config"))
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(file.path()).unwrap(),
+        "<133>kmsg-conf: from config"
+    );
+}
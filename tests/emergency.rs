@@ -0,0 +1,51 @@
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+// `emergency_log()` has global state, so it is tested in a separate process.
+#[test]
+fn socket_backend() {
+    let syslogd = support::FakeSyslogd::unix();
+    let missing = syslogd.path().with_file_name("missing");
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog(
+            "emergency-test",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Local6,
+        )
+        .socket_paths(&[missing.to_str().unwrap(), syslogd.path().to_str().unwrap()])
+        .build();
+    appender.activate().unwrap();
+
+    log4rs_syslog::emergency_log(log4rs_syslog::Severity::Crit, b"from signal handler").unwrap();
+    let received = syslogd.recv();
+    assert_eq!(received.facility, log4rs_syslog::Facility::Local6);
+    assert_eq!(received.severity, log4rs_syslog::Severity::Crit);
+    assert_eq!(received.app_name, Some(String::from("emergency-test")));
+    assert_eq!(received.proc_id, Some(std::process::id().to_string()));
+    assert_eq!(received.text(), "from signal handler");
+
+    // Configuration of the previously activated appender is restored when libc appender is dropped.
+    let libc_appender = log4rs_syslog::SyslogAppender::builder()
+        .openlog(
+            "emergency-libc",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::User,
+        )
+        .build();
+    libc_appender.activate().unwrap();
+    drop(libc_appender);
+
+    log4rs_syslog::emergency_log(log4rs_syslog::Severity::Alert, b"after libc appender").unwrap();
+    let received = syslogd.recv();
+    assert_eq!(received.severity, log4rs_syslog::Severity::Alert);
+    assert_eq!(received.app_name, Some(String::from("emergency-test")));
+    assert_eq!(received.text(), "after libc appender");
+
+    drop(appender);
+    let err = log4rs_syslog::emergency_log(log4rs_syslog::Severity::Crit, b"no appenders").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotConnected);
+}
@@ -0,0 +1,63 @@
+extern crate libc;
+extern crate log4rs_syslog;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct ForkCounter(Arc<AtomicUsize>);
+
+impl log4rs_syslog::SyslogBackend for ForkCounter {
+    fn open(&self, _ident: Option<&str>) -> Result<(), log4rs_syslog::SyslogError> {
+        Ok(())
+    }
+
+    fn send(&self, _priority: libc::c_int, _message: &[u8]) -> Result<(), log4rs_syslog::SyslogError> {
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), log4rs_syslog::SyslogError> {
+        Ok(())
+    }
+
+    fn close(&self) {}
+
+    fn after_fork(&self) {
+        let _ = self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// `after_fork()` affects all appenders of the process, so it is tested in a separate process.
+#[test]
+fn custom_backend() {
+    let activated_count = Arc::new(AtomicUsize::new(0));
+    let activated = log4rs_syslog::SyslogAppender::builder()
+        .backend(Box::new(ForkCounter(activated_count.clone())))
+        .build();
+    activated.activate().unwrap();
+
+    let idle_count = Arc::new(AtomicUsize::new(0));
+    let _idle = log4rs_syslog::SyslogAppender::builder()
+        .backend(Box::new(ForkCounter(idle_count.clone())))
+        .build();
+
+    log4rs_syslog::install_fork_handlers().unwrap();
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork() failed"),
+
+        0 => {
+            log4rs_syslog::after_fork();
+            let status = activated_count.load(Ordering::SeqCst) * 10 + idle_count.load(Ordering::SeqCst);
+            unsafe { libc::_exit(status as libc::c_int) };
+        },
+
+        child => {
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+            assert!(libc::WIFEXITED(status));
+            assert_eq!(libc::WEXITSTATUS(status), 10);
+        },
+    }
+
+    // Nothing is called in the parent.
+    assert_eq!(activated_count.load(Ordering::SeqCst), 0);
+}
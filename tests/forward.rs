@@ -0,0 +1,119 @@
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+use std::process::{Command, Stdio};
+
+fn spawn(script: &str, stdout: Stdio, stderr: Stdio) -> std::process::Child {
+    Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()
+        .unwrap()
+}
+
+// Stdout and stderr are forwarded by different threads, sort messages by text.
+fn recv_sorted(syslogd: &support::FakeSyslogd, count: usize) -> Vec<log4rs_syslog::SyslogMessage> {
+    let mut messages = (0..count).map(|_| syslogd.recv()).collect::<Vec<_>>();
+    messages.sort_by(|a, b| a.message.cmp(&b.message));
+    messages
+}
+
+fn severities(messages: &[log4rs_syslog::SyslogMessage]) -> Vec<(log4rs_syslog::Severity, String)> {
+    messages
+        .iter()
+        .map(|message| (message.severity, message.text().into_owned()))
+        .collect()
+}
+
+#[test]
+fn stdout_and_stderr() {
+    let syslogd = support::FakeSyslogd::unix();
+    let mut child = spawn(
+        "echo 'out line'; echo '<4>out prefixed'; echo 'err line' >&2",
+        Stdio::piped(),
+        Stdio::piped(),
+    );
+
+    let forwarding = log4rs_syslog::forward_child(
+        &mut child,
+        log4rs_syslog::ForwardOptions::new()
+            .facility(log4rs_syslog::Facility::Local5)
+            .stdout_severity(log4rs_syslog::Severity::Notice)
+            .socket_paths(&[syslogd.path().to_str().unwrap()]),
+    ).unwrap();
+    forwarding.join();
+    assert!(child.wait().unwrap().success());
+
+    let messages = recv_sorted(&syslogd, 3);
+    for message in &messages {
+        assert_eq!(message.facility, log4rs_syslog::Facility::Local5);
+        assert_eq!(message.app_name, Some(String::from("sh")));
+    }
+    assert_eq!(
+        severities(&messages),
+        vec![
+            (log4rs_syslog::Severity::Notice, String::from("<4>out prefixed")),
+            (log4rs_syslog::Severity::Err, String::from("err line")),
+            (log4rs_syslog::Severity::Notice, String::from("out line")),
+        ]
+    );
+}
+
+#[test]
+fn sd_daemon_prefix() {
+    let syslogd = support::FakeSyslogd::unix();
+    let mut child = spawn(
+        "echo '<4>warning'; echo '<15>debug with facility'; echo '<x>not a prefix'; echo '<3>error' >&2",
+        Stdio::piped(),
+        Stdio::piped(),
+    );
+
+    let forwarding = log4rs_syslog::forward_child(
+        &mut child,
+        log4rs_syslog::ForwardOptions::new()
+            .ident("helper")
+            .sd_daemon_prefix(true)
+            .socket_paths(&[syslogd.path().to_str().unwrap()]),
+    ).unwrap();
+    forwarding.join();
+    assert!(child.wait().unwrap().success());
+
+    let messages = recv_sorted(&syslogd, 4);
+    assert_eq!(messages[0].app_name, Some(String::from("helper")));
+    assert_eq!(
+        severities(&messages),
+        vec![
+            (log4rs_syslog::Severity::Info, String::from("<x>not a prefix")),
+            (log4rs_syslog::Severity::Debug, String::from("debug with facility")),
+            (log4rs_syslog::Severity::Err, String::from("error")),
+            (log4rs_syslog::Severity::Warning, String::from("warning")),
+        ]
+    );
+}
+
+#[test]
+fn not_piped() {
+    let mut child = spawn("true", Stdio::null(), Stdio::null());
+    let err = log4rs_syslog::forward_child(&mut child, log4rs_syslog::ForwardOptions::new()).unwrap_err();
+    assert!(err.is_config_error());
+    child.wait().unwrap();
+}
+
+#[test]
+fn kern_facility() {
+    let mut child = spawn("true", Stdio::piped(), Stdio::null());
+    let err = log4rs_syslog::forward_child(
+        &mut child,
+        log4rs_syslog::ForwardOptions::new().facility(log4rs_syslog::Facility::Kern),
+    ).unwrap_err();
+    assert!(err.is_config_error());
+    assert!(child.stdout.is_some());
+    child.wait().unwrap();
+}
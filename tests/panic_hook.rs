@@ -0,0 +1,39 @@
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+// Panic hook is global, so it is tested in a separate process.
+#[test]
+fn socket_backend() {
+    let syslogd = support::FakeSyslogd::unix();
+    log4rs_syslog::install_panic_hook_with(
+        log4rs_syslog::SyslogAppender::builder()
+            .openlog(
+                "panic-test",
+                log4rs_syslog::LogOption::empty(),
+                log4rs_syslog::Facility::Local5,
+            )
+            .socket_paths(&[syslogd.path().to_str().unwrap()]),
+        log4rs_syslog::PanicHookConfig::new().severity(log4rs_syslog::Severity::Alert),
+    ).unwrap();
+
+    let result = std::thread::Builder::new()
+        .name(String::from("doomed"))
+        .spawn(|| panic!("boom"))
+        .unwrap()
+        .join();
+    assert!(result.is_err());
+
+    let received = syslogd.recv();
+    assert_eq!(received.facility, log4rs_syslog::Facility::Local5);
+    assert_eq!(received.severity, log4rs_syslog::Severity::Alert);
+    assert_eq!(received.app_name, Some(String::from("panic-test")));
+    assert!(
+        received.text().starts_with("thread 'doomed' panicked at 'boom', tests/panic_hook.rs:"),
+        "{:?}",
+        received
+    );
+}
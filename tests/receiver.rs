@@ -0,0 +1,184 @@
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+use std::io::{Read, Write};
+use std::sync::Mutex;
+
+static RECORDS: Mutex<Vec<(log::Level, String, String)>> = Mutex::new(Vec::new());
+
+struct Recorder;
+
+impl log::Log for Recorder {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        RECORDS.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static RECORDER: Recorder = Recorder;
+
+fn init() {
+    // Tests run in parallel, only the first one installs logger.
+    if log::set_logger(&RECORDER).is_ok() {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+}
+
+// Records of different tests are told apart by target.
+fn wait_records(target: &str, count: usize) -> Vec<(log::Level, String)> {
+    for _ in 0..100 {
+        let records = RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.1 == target)
+            .map(|record| (record.0, record.2.clone()))
+            .collect::<Vec<_>>();
+        if records.len() >= count {
+            return records;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    panic!("no records with target {:?}", target);
+}
+
+fn appender(ident: &str, socket_path: &std::path::Path) -> log4rs_syslog::SyslogAppender {
+    log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+        .openlog(
+            ident,
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Local2,
+        )
+        .socket_paths(&[socket_path.to_str().unwrap()])
+        .build()
+}
+
+#[test]
+fn unix() {
+    init();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log");
+    let handle = log4rs_syslog::SyslogReceiver::unix(&path)
+        .unwrap()
+        .target_prefix("legacy::")
+        .spawn()
+        .unwrap();
+    assert_eq!(handle.local_addr(), None);
+
+    let appender = appender("unix-helper", &path);
+    support::append(&appender, log::Level::Error, "failed");
+    support::append(&appender, log::Level::Warn, "degraded");
+
+    assert_eq!(
+        wait_records("legacy::unix-helper", 2),
+        vec![
+            (log::Level::Error, String::from("failed")),
+            (log::Level::Warn, String::from("degraded")),
+        ]
+    );
+
+    handle.stop();
+    assert!(!path.exists());
+}
+
+#[test]
+fn udp() {
+    init();
+    let handle = log4rs_syslog::SyslogReceiver::udp("127.0.0.1:0".parse().unwrap())
+        .unwrap()
+        .spawn()
+        .unwrap();
+    let addr = handle.local_addr().unwrap();
+
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .send_to(b"<14>1 2003-10-11T22:14:15.003Z host udp-helper 42 - - plain info", addr)
+        .unwrap();
+    socket
+        .send_to(b"<191>Oct 11 22:14:15 udp-helper[42]: debug details", addr)
+        .unwrap();
+    socket.send_to(b"<13>Oct 11 22:14:15 no tag here", addr).unwrap();
+
+    assert_eq!(
+        wait_records("udp-helper", 2),
+        vec![
+            (log::Level::Info, String::from("plain info")),
+            (log::Level::Debug, String::from("debug details")),
+        ]
+    );
+    assert!(wait_records("syslog", 1)
+        .iter()
+        .any(|record| record.1.contains("no tag here")));
+}
+
+#[test]
+fn tcp() {
+    init();
+    let handle = log4rs_syslog::SyslogReceiver::tcp("127.0.0.1:0".parse().unwrap())
+        .unwrap()
+        .spawn()
+        .unwrap();
+    let addr = handle.local_addr().unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    let frame = "<14>1 2003-10-11T22:14:15.003Z host tcp-helper 42 - - multi\nline";
+    write!(stream, "{} {}", frame.len(), frame).unwrap();
+    stream
+        .write_all(b"<10>Oct 11 22:14:15 tcp-lf: critical\n<12>Oct 11 22:14:15 tcp-lf: split")
+        .unwrap();
+    stream.write_all(b" warning\n").unwrap();
+
+    assert_eq!(
+        wait_records("tcp-helper", 1),
+        vec![(log::Level::Info, String::from("multi\nline"))]
+    );
+    assert_eq!(
+        wait_records("tcp-lf", 2),
+        vec![
+            (log::Level::Error, String::from("critical")),
+            (log::Level::Warn, String::from("split warning")),
+        ]
+    );
+}
+
+#[test]
+fn tcp_max_connections() {
+    init();
+    let handle = log4rs_syslog::SyslogReceiver::tcp("127.0.0.1:0".parse().unwrap())
+        .unwrap()
+        .max_connections(1)
+        .spawn()
+        .unwrap();
+    let addr = handle.local_addr().unwrap();
+
+    let mut first = std::net::TcpStream::connect(addr).unwrap();
+    first.write_all(b"<14>Oct 11 22:14:15 tcp-limit: accepted\n").unwrap();
+    assert_eq!(wait_records("tcp-limit", 1).len(), 1);
+
+    let mut second = std::net::TcpStream::connect(addr).unwrap();
+    second
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let mut buf = [0; 1];
+    match second.read(&mut buf) {
+        Ok(len) => assert_eq!(len, 0),
+        Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset),
+    }
+
+    first.write_all(b"<14>Oct 11 22:14:15 tcp-limit: still served\n").unwrap();
+    assert_eq!(wait_records("tcp-limit", 2).len(), 2);
+}
@@ -0,0 +1,170 @@
+//! Fake syslog daemon and other helpers for integration tests.
+
+#![allow(dead_code)]
+
+use std;
+use std::io::{BufRead, Read};
+
+use log;
+use log4rs_syslog;
+use tempfile;
+
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Append record with given level and message, panic on failure.
+pub fn append(appender: &log4rs_syslog::SyslogAppender, level: log::Level, message: &str) {
+    use log4rs::append::Append;
+
+    appender
+        .append(
+            &log::Record::builder()
+                .level(level)
+                .args(format_args!("{}", message))
+                .build(),
+        )
+        .unwrap();
+}
+
+fn read_tcp_frames(stream: std::net::TcpStream, sender: std::sync::mpsc::Sender<String>) {
+    let mut reader = std::io::BufReader::new(stream);
+    loop {
+        let starts_with_digit = match reader.fill_buf() {
+            Ok(buf) => match buf.first() {
+                Some(c) => c.is_ascii_digit(),
+                None => return,
+            },
+            Err(_) => return,
+        };
+
+        let mut frame = Vec::new();
+        if starts_with_digit {
+            // Octet counting.
+            if reader.read_until(b' ', &mut frame).is_err() {
+                return;
+            }
+            let len: usize = String::from_utf8_lossy(&frame).trim().parse().unwrap();
+            frame.resize(len, 0);
+            if reader.read_exact(&mut frame).is_err() {
+                return;
+            }
+        } else {
+            // Non-transparent framing.
+            if reader.read_until(b'\n', &mut frame).is_err() {
+                return;
+            }
+            let _ = frame.pop();
+        }
+
+        if sender.send(String::from_utf8_lossy(&frame).into_owned()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Fake syslog daemon listening on a temporary Unix datagram socket, UDP or TCP port on localhost.
+pub struct FakeSyslogd {
+    receiver: std::sync::mpsc::Receiver<String>,
+    path: Option<std::path::PathBuf>,
+    addr: Option<std::net::SocketAddr>,
+    _dir: Option<tempfile::TempDir>,
+}
+
+impl FakeSyslogd {
+    fn new(receiver: std::sync::mpsc::Receiver<String>) -> Self {
+        Self {
+            receiver,
+            path: None,
+            addr: None,
+            _dir: None,
+        }
+    }
+
+    pub fn unix() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _ = std::thread::spawn(move || {
+            let mut buf = vec![0; 65536];
+            while let Ok(len) = socket.recv(&mut buf) {
+                if sender.send(String::from_utf8_lossy(&buf[..len]).into_owned()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            path: Some(path),
+            _dir: Some(dir),
+            ..Self::new(receiver)
+        }
+    }
+
+    pub fn udp() -> Self {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _ = std::thread::spawn(move || {
+            let mut buf = vec![0; 65536];
+            while let Ok(len) = socket.recv(&mut buf) {
+                if sender.send(String::from_utf8_lossy(&buf[..len]).into_owned()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            addr: Some(addr),
+            ..Self::new(receiver)
+        }
+    }
+
+    pub fn tcp() -> Self {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _ = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let sender = sender.clone();
+                match stream {
+                    Ok(stream) => {
+                        let _ = std::thread::spawn(move || read_tcp_frames(stream, sender));
+                    },
+                    Err(_) => return,
+                }
+            }
+        });
+
+        Self {
+            addr: Some(addr),
+            ..Self::new(receiver)
+        }
+    }
+
+    /// Path of Unix socket.
+    pub fn path(&self) -> &std::path::Path {
+        self.path.as_ref().unwrap()
+    }
+
+    /// Address of UDP or TCP socket.
+    pub fn addr(&self) -> std::net::SocketAddr {
+        self.addr.unwrap()
+    }
+
+    /// Wait for the next record and return it as is.
+    pub fn recv_raw(&self) -> String {
+        self.receiver
+            .recv_timeout(TIMEOUT)
+            .expect("No record received")
+    }
+
+    /// Wait for the next record and parse it.
+    pub fn recv(&self) -> log4rs_syslog::SyslogMessage {
+        let record = self.recv_raw();
+        log4rs_syslog::parse_message(record.as_bytes())
+            .unwrap_or_else(|err| panic!("Unable to parse {:?}: {}", record, err))
+    }
+}
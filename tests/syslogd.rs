@@ -0,0 +1,246 @@
+extern crate log;
+extern crate log4rs;
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+fn appender(ident: Option<&str>, pattern: &str, socket_path: &str) -> log4rs_syslog::SyslogAppender {
+    build(ident, pattern, log4rs_syslog::SyslogAppender::builder().socket_paths(&[socket_path]))
+}
+
+fn network_appender(
+    ident: &str,
+    pattern: &str,
+    backend: log4rs_syslog::SocketBackend,
+) -> log4rs_syslog::SyslogAppender {
+    build(
+        Some(ident),
+        pattern,
+        log4rs_syslog::SyslogAppender::builder().backend(Box::new(backend)),
+    )
+}
+
+fn build(
+    ident: Option<&str>,
+    pattern: &str,
+    builder: log4rs_syslog::SyslogAppenderBuilder,
+) -> log4rs_syslog::SyslogAppender {
+    let builder = builder.encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new(pattern)));
+
+    match ident {
+        Some(ident) => builder.openlog(
+            ident,
+            log4rs_syslog::LogOption::LOG_PID,
+            log4rs_syslog::Facility::Local4,
+        ),
+        None => builder,
+    }.build()
+}
+
+fn pid() -> Option<String> {
+    Some(std::process::id().to_string())
+}
+
+#[test]
+fn unix_rfc3164() {
+    let syslogd = support::FakeSyslogd::unix();
+    let appender = appender(Some("unix-test"), "{m}", syslogd.path().to_str().unwrap());
+
+    support::append(&appender, log::Level::Warn, "over unix socket");
+    let received = syslogd.recv();
+    assert_eq!(received.facility, log4rs_syslog::Facility::Local4);
+    assert_eq!(received.severity, log4rs_syslog::Severity::Warning);
+    assert_eq!(received.version, None);
+    assert_eq!(received.timestamp.as_ref().map(String::len), Some(15));
+    assert_eq!(received.hostname, None);
+    assert_eq!(received.app_name, Some(String::from("unix-test")));
+    assert_eq!(received.proc_id, pid());
+    assert_eq!(received.text(), "over unix socket");
+}
+
+#[test]
+fn unix_default_facility() {
+    let syslogd = support::FakeSyslogd::unix();
+    let appender = appender(None, "{m}", syslogd.path().to_str().unwrap());
+
+    support::append(&appender, log::Level::Error, "no openlog");
+    let received = syslogd.recv();
+    assert_eq!(received.facility, log4rs_syslog::Facility::User);
+    assert_eq!(received.severity, log4rs_syslog::Severity::Err);
+    assert!(received.app_name.is_some());
+}
+
+#[test]
+fn unix_message_too_large() {
+    use log4rs::append::Append;
+
+    let syslogd = support::FakeSyslogd::unix();
+    let appender = appender(Some("large-test"), "{m}", syslogd.path().to_str().unwrap());
+
+    let message = "x".repeat(1 << 20);
+    let err = appender
+        .append(&log::Record::builder().args(format_args!("{}", message)).build())
+        .unwrap_err();
+    match err.downcast_ref::<log4rs_syslog::SyslogError>() {
+        Some(&log4rs_syslog::SyslogError::MessageTooLarge { size, limit }) => {
+            assert!(size > message.len());
+            assert!(limit < size);
+        },
+        other => panic!("Unexpected error: {:?}", other),
+    }
+
+    support::append(&appender, log::Level::Info, "small enough");
+    assert_eq!(syslogd.recv().text(), "small enough");
+}
+
+#[test]
+fn udp_rfc5424() {
+    let syslogd = support::FakeSyslogd::udp();
+    let appender = network_appender("udp-test", "{m}", log4rs_syslog::SocketBackend::udp(syslogd.addr()));
+
+    support::append(&appender, log::Level::Info, "over udp");
+    let received = syslogd.recv();
+    assert_eq!(received.facility, log4rs_syslog::Facility::Local4);
+    assert_eq!(received.severity, log4rs_syslog::Severity::Info);
+    assert_eq!(received.version, Some(1));
+    assert!(received.timestamp.as_ref().unwrap().contains('T'), "{:?}", received);
+    assert!(received.hostname.is_some());
+    assert_eq!(received.app_name, Some(String::from("udp-test")));
+    assert_eq!(received.proc_id, pid());
+    assert_eq!(received.msg_id, None);
+    assert_eq!(received.text(), "over udp");
+}
+
+#[test]
+fn udp_rfc3164() {
+    let syslogd = support::FakeSyslogd::udp();
+    let appender = network_appender(
+        "udp-test",
+        "{m}",
+        log4rs_syslog::SocketBackend::udp(syslogd.addr()).format(log4rs_syslog::Format::Rfc3164),
+    );
+
+    support::append(&appender, log::Level::Debug, "bsd over udp");
+    let received = syslogd.recv();
+    assert_eq!(received.severity, log4rs_syslog::Severity::Debug);
+    assert_eq!(received.version, None);
+    assert!(received.hostname.is_some());
+    assert_eq!(received.app_name, Some(String::from("udp-test")));
+    assert_eq!(received.text(), "bsd over udp");
+}
+
+#[test]
+fn tcp_octet_counting() {
+    let syslogd = support::FakeSyslogd::tcp();
+    let appender = network_appender(
+        "tcp-test",
+        "{m}",
+        log4rs_syslog::SocketBackend::tcp(syslogd.addr(), log4rs_syslog::Framing::OctetCounting),
+    );
+
+    support::append(&appender, log::Level::Error, "first\nline");
+    support::append(&appender, log::Level::Warn, "second");
+    let raw = syslogd.recv_raw();
+    assert!(raw.starts_with("<163>1 "), "{:?}", raw);
+    assert!(raw.ends_with(" - - first\nline"), "{:?}", raw);
+
+    let received = syslogd.recv();
+    assert_eq!(received.severity, log4rs_syslog::Severity::Warning);
+    assert_eq!(received.app_name, Some(String::from("tcp-test")));
+    assert_eq!(received.text(), "second");
+}
+
+#[test]
+fn tcp_non_transparent() {
+    let syslogd = support::FakeSyslogd::tcp();
+    let appender = network_appender(
+        "tcp-test",
+        "{m}{n}",
+        log4rs_syslog::SocketBackend::tcp(syslogd.addr(), log4rs_syslog::Framing::NonTransparent),
+    );
+
+    support::append(&appender, log::Level::Error, "first");
+    support::append(&appender, log::Level::Info, "second");
+    assert_eq!(syslogd.recv().text(), "first");
+    assert_eq!(syslogd.recv().text(), "second");
+}
+
+#[test]
+fn unix_socket_paths() {
+    let syslogd = support::FakeSyslogd::unix();
+    let missing = syslogd.path().with_file_name("missing");
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+        .openlog(
+            "paths-test",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Local4,
+        )
+        .socket_paths(&[missing.to_str().unwrap(), syslogd.path().to_str().unwrap()])
+        .announce(true)
+        .build();
+    assert_eq!(appender.destination(), None);
+
+    support::append(&appender, log::Level::Info, "after fallback");
+    assert_eq!(
+        appender.destination(),
+        Some(syslogd.path().display().to_string())
+    );
+
+    let announcement = syslogd.recv();
+    assert_eq!(announcement.severity, log4rs_syslog::Severity::Info);
+    assert_eq!(
+        announcement.text(),
+        format!("log4rs-syslog: using syslog socket at {}", syslogd.path().display())
+    );
+    assert_eq!(syslogd.recv().text(), "after fallback");
+}
+
+#[test]
+fn unix_socket_paths_without_announcement() {
+    let syslogd = support::FakeSyslogd::unix();
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+        .socket_paths(&[syslogd.path().to_str().unwrap()])
+        .build();
+
+    support::append(&appender, log::Level::Info, "first message");
+    assert_eq!(syslogd.recv().text(), "first message");
+}
+
+#[test]
+fn invalid_socket_paths() {
+    let err = log4rs_syslog::SyslogAppender::builder()
+        .socket_paths::<&str>(&[])
+        .try_build()
+        .unwrap_err();
+    assert!(err.is_config_error());
+    assert!(err.to_string().contains("\"socket_paths\""), "{}", err);
+
+    let err = log4rs_syslog::SyslogAppender::builder()
+        .socket_paths(&["/dev/log", ""])
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("\"socket_paths[1]\""), "{}", err);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn unix_abstract_socket() {
+    use std::os::linux::net::SocketAddrExt;
+
+    let name = format!("log4rs-syslog-test-{}", std::process::id());
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(&name).unwrap();
+    let socket = std::os::unix::net::UnixDatagram::bind_addr(&addr).unwrap();
+
+    let appender = appender(Some("abstract-test"), "{m}", &format!("@{}", name));
+    support::append(&appender, log::Level::Warn, "over abstract socket");
+
+    let mut buf = vec![0; 4096];
+    let len = socket.recv(&mut buf).unwrap();
+    let received = log4rs_syslog::parse_message(&buf[..len]).unwrap();
+    assert_eq!(received.app_name, Some(String::from("abstract-test")));
+    assert_eq!(received.text(), "over abstract socket");
+    assert_eq!(appender.destination(), Some(format!("@{}", name)));
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "testing")]
+
+extern crate log;
+extern crate log4rs;
+#[macro_use]
+extern crate log4rs_syslog;
+extern crate tempfile;
+
+mod support;
+
+#[test]
+fn capture() {
+    let capture = log4rs_syslog::testing::CaptureBackend::new();
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}{n}")))
+        .openlog(
+            "capture",
+            log4rs_syslog::LogOption::empty(),
+            log4rs_syslog::Facility::Local2,
+        )
+        .level_map(Box::new(|level| match level {
+            log::Level::Error => log4rs_syslog::Severity::Crit.into(),
+            _ => log4rs_syslog::Severity::Info.into(),
+        }))
+        .backend(Box::new(capture.clone()))
+        .build();
+
+    support::append(&appender, log::Level::Error, "connection lost");
+    support::append(&appender, log::Level::Info, "two\nlines");
+
+    assert_logged!(capture, log4rs_syslog::Severity::Crit, "connection");
+    assert_not_logged!(capture, log4rs_syslog::Severity::Err, "connection");
+    assert_snapshot!(
+        capture,
+        "
+            local2.crit capture: connection lost
+            local2.info capture: two\\nlines
+        "
+    );
+
+    let messages = capture.messages();
+    assert_eq!(messages[0].ident, Some(String::from("capture")));
+    assert_eq!(messages[1].message, b"two\nlines\n".to_vec());
+
+    capture.clear();
+    assert_snapshot!(capture, "");
+}
+
+#[test]
+fn capture_without_openlog() {
+    let capture = log4rs_syslog::testing::CaptureBackend::new();
+    let appender = log4rs_syslog::SyslogAppender::builder()
+        .encoder(Box::new(log4rs::encode::pattern::PatternEncoder::new("{m}")))
+        .backend(Box::new(capture.clone()))
+        .build();
+
+    support::append(&appender, log::Level::Warn, "no ident");
+    assert_snapshot!(capture, "user.warning: no ident");
+}
+
+#[test]
+#[should_panic(expected = "no Err message containing \"missing\"")]
+fn assert_logged_fails() {
+    let capture = log4rs_syslog::testing::CaptureBackend::new();
+    assert_logged!(capture, log4rs_syslog::Severity::Err, "missing");
+}